@@ -0,0 +1,68 @@
+use {
+    advent_of_code_2018::{day3::day3_part1, grid::GrowOnlyGrid},
+    criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput},
+};
+
+/// Claim-like rectangles (left, top, width, height) in a strip of fabric that drift right, so that
+/// most of them push out the grid's bounding box a little while the area of the grid only grows
+/// linearly with the number of claims.
+fn drifting_claims(count: usize) -> Vec<(usize, usize, usize, usize)> {
+    let mut seed = 0x2545_f491u32;
+    let mut next = move |bound: u32| {
+        seed ^= seed << 13;
+        seed ^= seed >> 17;
+        seed ^= seed << 5;
+        (seed % bound) as usize
+    };
+    (0..count)
+        .map(|i| (i + next(20), next(100), 1 + next(30), 1 + next(30)))
+        .collect()
+}
+
+fn bench_incremental_claims(c: &mut Criterion) {
+    let mut group = c.benchmark_group("grow_only_grid_incremental_claims");
+    for &count in &[250, 500, 1000, 2000] {
+        let claims = drifting_claims(count);
+        group.throughput(Throughput::Elements(count as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(count), &claims, |b, claims| {
+            b.iter(|| {
+                let mut grid = GrowOnlyGrid::<u8>::new_with(0, 0, Default::default);
+                for &(left, top, width, height) in claims {
+                    let (right, bottom) = (left + width, top + height);
+                    grid.grow_with(right, bottom, Default::default);
+                    for y in top..bottom {
+                        for x in left..right {
+                            grid[(x, y)] += 1;
+                        }
+                    }
+                }
+                grid
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_day3_part1(c: &mut Criterion) {
+    let mut group = c.benchmark_group("day3_part1");
+    group.bench_function("input", |b| {
+        b.iter(|| day3_part1(include_str!("../input/2018/day3.txt")).unwrap())
+    });
+    for &count in &[250, 500, 1000, 2000] {
+        let input = drifting_claims(count)
+            .into_iter()
+            .enumerate()
+            .map(|(id, (left, top, width, height))| {
+                format!("#{} @ {},{}: {}x{}\n", id + 1, left, top, width, height)
+            })
+            .collect::<String>();
+        group.throughput(Throughput::Elements(count as u64));
+        group.bench_with_input(BenchmarkId::new("drifting", count), &input, |b, input| {
+            b.iter(|| day3_part1(input).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_incremental_claims, bench_day3_part1);
+criterion_main!(benches);
@@ -1,61 +1,751 @@
 use {
     aoc_runner_derive::aoc,
-    std::{collections::HashMap, str::Split},
+    std::{
+        collections::HashMap,
+        error::Error,
+        fmt::{Debug, Display, Formatter, Result as FmtResult},
+        hash::Hash,
+        iter::Enumerate,
+        marker::PhantomData,
+        num::ParseIntError,
+        str::{FromStr, Lines},
+    },
 };
 
-struct Day1EntryIterator<'i> {
-    input: Split<'i, char>,
+/// An integer type that frequencies can be accumulated in.
+///
+/// Every operation reports overflow with `None` instead of panicking or wrapping.
+pub trait Frequency: Clone + Debug + Eq + Hash + Ord + FromStr {
+    fn zero() -> Self;
+    fn checked_add(&self, rhs: &Self) -> Option<Self>;
+    fn checked_sub(&self, rhs: &Self) -> Option<Self>;
+    fn checked_div(&self, rhs: &Self) -> Option<Self>;
+    /// The non-negative remainder of dividing `self` by `rhs`.
+    fn checked_rem_euclid(&self, rhs: &Self) -> Option<Self>;
+    /// How many times `step` must be added to `self` to reach `other`, or `None` if that isn't a
+    /// whole number of times or doesn't fit in a `usize`. This must not overflow even when
+    /// `other - self` doesn't fit in `Self`.
+    fn steps_to(&self, other: &Self, step: &Self) -> Option<usize>;
+    fn to_usize(&self) -> Option<usize>;
 }
 
-impl<'i> Day1EntryIterator<'i> {
+macro_rules! impl_frequency_for_primitive {
+    ($($t: ty: $unsigned: ty),*) => {$(
+        impl Frequency for $t {
+            fn zero() -> Self {
+                0
+            }
+
+            fn checked_add(&self, rhs: &Self) -> Option<Self> {
+                <$t>::checked_add(*self, *rhs)
+            }
+
+            fn checked_sub(&self, rhs: &Self) -> Option<Self> {
+                <$t>::checked_sub(*self, *rhs)
+            }
+
+            fn checked_div(&self, rhs: &Self) -> Option<Self> {
+                <$t>::checked_div(*self, *rhs)
+            }
+
+            fn checked_rem_euclid(&self, rhs: &Self) -> Option<Self> {
+                <$t>::checked_rem_euclid(*self, *rhs)
+            }
+
+            fn steps_to(&self, other: &Self, step: &Self) -> Option<usize> {
+                use std::convert::TryFrom;
+
+                // The distance between any two values of `$t` fits in `$unsigned`, as does the
+                // magnitude of `step`, so work with those instead.
+                let (distance, step) = match (step.signum(), other >= self) {
+                    (1, true) => (other.wrapping_sub(*self), *step),
+                    (-1, false) => (self.wrapping_sub(*other), step.wrapping_neg()),
+                    _ => return None,
+                };
+                let (distance, step) = (distance as $unsigned, step as $unsigned);
+                if distance % step != 0 {
+                    return None;
+                }
+                usize::try_from(distance / step).ok()
+            }
+
+            fn to_usize(&self) -> Option<usize> {
+                use std::convert::TryFrom;
+
+                usize::try_from(*self).ok()
+            }
+        }
+    )*};
+}
+
+impl_frequency_for_primitive!(i32: u32, i64: u64, i128: u128);
+
+#[cfg(feature = "bigint")]
+impl Frequency for num_bigint::BigInt {
+    fn zero() -> Self {
+        num_traits::Zero::zero()
+    }
+
+    fn checked_add(&self, rhs: &Self) -> Option<Self> {
+        Some(self + rhs)
+    }
+
+    fn checked_sub(&self, rhs: &Self) -> Option<Self> {
+        Some(self - rhs)
+    }
+
+    fn checked_div(&self, rhs: &Self) -> Option<Self> {
+        num_traits::CheckedDiv::checked_div(self, rhs)
+    }
+
+    fn checked_rem_euclid(&self, rhs: &Self) -> Option<Self> {
+        use num_traits::{Signed, Zero};
+
+        if rhs.is_zero() {
+            return None;
+        }
+        let modulus = rhs.abs();
+        Some(((self % &modulus) + &modulus) % &modulus)
+    }
+
+    fn steps_to(&self, other: &Self, step: &Self) -> Option<usize> {
+        use num_traits::Zero;
+
+        let distance = other - self;
+        if step.is_zero() || !(&distance % step).is_zero() {
+            return None;
+        }
+        num_traits::ToPrimitive::to_usize(&(distance / step))
+    }
+
+    fn to_usize(&self) -> Option<usize> {
+        num_traits::ToPrimitive::to_usize(self)
+    }
+}
+
+/// Returned when a frequency no longer fits in the accumulator type.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct FrequencyOverflow;
+
+impl Display for FrequencyOverflow {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "frequency overflowed its accumulator type")
+    }
+}
+
+/// Returned when searching for a repeated frequency fails.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CycleSearchError {
+    Overflow(FrequencyOverflow),
+    /// More changes would need to be applied than a `usize` can count.
+    StepCountOverflow,
+}
+
+impl Display for CycleSearchError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            CycleSearchError::Overflow(e) => write!(f, "{}", e),
+            CycleSearchError::StepCountOverflow => write!(f, "step count overflowed a `usize`"),
+        }
+    }
+}
+
+impl From<FrequencyOverflow> for CycleSearchError {
+    fn from(e: FrequencyOverflow) -> Self {
+        CycleSearchError::Overflow(e)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Day1ParseErrorKind<E = ParseIntError> {
+    EmptyLine,
+    MissingSign,
+    InvalidNumber(E),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Day1ParseError<E = ParseIntError> {
+    /// 1-based line number of the offending line
+    pub line_number: usize,
+    pub line: String,
+    pub kind: Day1ParseErrorKind<E>,
+}
+
+impl<E: Display> Display for Day1ParseError<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        use self::Day1ParseErrorKind::*;
+
+        write!(f, "line {} ({:?}): ", self.line_number, self.line)?;
+        match &self.kind {
+            EmptyLine => write!(f, "unexpected empty line"),
+            MissingSign => write!(f, "expected change to start with '+' or '-'"),
+            InvalidNumber(e) => write!(f, "invalid number: {}", e),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Day1Error<E = ParseIntError> {
+    Parse(Day1ParseError<E>),
+    Overflow(FrequencyOverflow),
+    /// More changes would need to be applied than a `usize` can count.
+    StepCountOverflow,
+    /// No frequency is ever reached twice, however many times the changes are applied.
+    NeverRepeats,
+}
+
+impl<E: Display> Display for Day1Error<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Day1Error::Parse(e) => write!(f, "{}", e),
+            Day1Error::Overflow(e) => write!(f, "{}", e),
+            Day1Error::StepCountOverflow => write!(f, "{}", CycleSearchError::StepCountOverflow),
+            Day1Error::NeverRepeats => write!(f, "no frequency ever repeats"),
+        }
+    }
+}
+
+impl<E: Debug + Display> Error for Day1ParseError<E> {}
+
+impl<E: Debug + Display> Error for Day1Error<E> {}
+
+impl<E> From<Day1ParseError<E>> for Day1Error<E> {
+    fn from(e: Day1ParseError<E>) -> Self {
+        Day1Error::Parse(e)
+    }
+}
+
+impl<E> From<FrequencyOverflow> for Day1Error<E> {
+    fn from(e: FrequencyOverflow) -> Self {
+        Day1Error::Overflow(e)
+    }
+}
+
+impl<E> From<CycleSearchError> for Day1Error<E> {
+    fn from(e: CycleSearchError) -> Self {
+        match e {
+            CycleSearchError::Overflow(e) => Day1Error::Overflow(e),
+            CycleSearchError::StepCountOverflow => Day1Error::StepCountOverflow,
+        }
+    }
+}
+
+pub struct Day1EntryIterator<'i, F = i32> {
+    lines: Enumerate<Lines<'i>>,
+    lenient: bool,
+    _frequency: PhantomData<F>,
+}
+
+impl<'i, F: FromStr> Day1EntryIterator<'i, F> {
+    /// Parses every line of `input` as a frequency change, yielding an error for anything else.
     pub fn new(input: &'i str) -> Self {
         Self {
-            input: input.split('\n'),
+            lines: input.lines().enumerate(),
+            lenient: false,
+            _frequency: PhantomData,
         }
     }
+
+    /// Like `new`, but skips blank lines and lines starting with `#`.
+    pub fn lenient(input: &'i str) -> Self {
+        Self {
+            lenient: true,
+            ..Self::new(input)
+        }
+    }
+}
+
+fn parse_change<F: FromStr>(change: &str) -> Result<F, Day1ParseErrorKind<F::Err>> {
+    use self::Day1ParseErrorKind::*;
+
+    match change.chars().next() {
+        Some('+') | Some('-') => (),
+        Some(_) => return Err(MissingSign),
+        None => return Err(EmptyLine),
+    }
+    change.parse().map_err(InvalidNumber)
 }
 
-impl<'i> Iterator for Day1EntryIterator<'i> {
-    type Item = i32;
+impl<'i, F: FromStr> Iterator for Day1EntryIterator<'i, F> {
+    type Item = Result<F, Day1ParseError<F::Err>>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let change = self.input.next()?;
-        let positive = match change.chars().next()? {
-            '+' => true,
-            '-' => false,
-            _ => unreachable!(),
-        };
-        let mut number: i32 = change[1..].parse().unwrap();
-        if !positive {
-            number = number.checked_mul(-1).unwrap();
+        loop {
+            let (line_idx, line) = self.lines.next()?;
+            let change = line.trim();
+            if self.lenient && (change.is_empty() || change.starts_with('#')) {
+                continue;
+            }
+            break Some(parse_change(change).map_err(|kind| Day1ParseError {
+                line_number: line_idx + 1,
+                line: line.to_owned(),
+                kind,
+            }));
         }
-        Some(number)
     }
 }
 
+/// Applies every change in `changes` to a starting frequency of zero.
+pub fn resulting_frequency<F: Frequency>(
+    changes: impl IntoIterator<Item = F>,
+) -> Result<F, FrequencyOverflow> {
+    changes.into_iter().try_fold(F::zero(), |total, change| {
+        total.checked_add(&change).ok_or(FrequencyOverflow)
+    })
+}
+
 #[aoc(day1, part1)]
-pub fn day1_part1(input: &str) -> i32 {
-    let mut total: i32 = 0;
-    for change in Day1EntryIterator::new(input) {
-        total = total.checked_add(change).unwrap();
+pub fn day1_part1(input: &str) -> Result<i32, Day1Error> {
+    day1_part1_as(input)
+}
+
+/// Like `day1_part1`, but parses changes as and accumulates in `F`.
+pub fn day1_part1_as<F: Frequency>(input: &str) -> Result<F, Day1Error<F::Err>> {
+    let changes = Day1EntryIterator::new(input).collect::<Result<Vec<F>, _>>()?;
+    Ok(resulting_frequency(changes)?)
+}
+
+/// A position in the endlessly repeated list of frequency changes.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ChangePosition {
+    /// Number of complete passes over the change list made before this position
+    pub pass: usize,
+    /// Number of changes applied so far in the current pass, where `0` is the initial frequency
+    pub change_num: usize,
+}
+
+impl ChangePosition {
+    fn from_steps(steps: usize, num_changes: usize) -> Self {
+        match steps {
+            0 => Self {
+                pass: 0,
+                change_num: 0,
+            },
+            steps => Self {
+                pass: (steps - 1) / num_changes,
+                change_num: (steps - 1) % num_changes + 1,
+            },
+        }
     }
-    total
 }
 
-#[aoc(day1, part2)]
-pub fn day1_part2(input: &str) -> i32 {
-    let changes = Day1EntryIterator::new(input).collect::<Vec<_>>();
-    let mut repeat_count: i32 = 0;
+/// Describes where the first repeated frequency in a calibration sequence occurred.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct FrequencyCycleReport<F = i32> {
+    /// The first frequency reached twice
+    pub frequency: F,
+    /// Where `frequency` was reached for the first time
+    pub first_reached: ChangePosition,
+    /// Where `frequency` was reached for the second time
+    pub repeated_at: ChangePosition,
+    /// Total number of changes applied up to and including the repeat
+    pub steps: usize,
+}
+
+/// Applies `changes` over and over until some frequency repeats.
+///
+/// This never returns if no frequency ever repeats, e.g., for `+1, +1`.
+pub fn frequency_cycle_report<F: Frequency>(
+    changes: &[F],
+) -> Result<FrequencyCycleReport<F>, CycleSearchError> {
+    let mut pass: usize = 0;
     let mut seen = HashMap::new();
-    let mut total: i32 = 0;
+    let mut total = F::zero();
+    seen.insert(
+        total.clone(),
+        ChangePosition {
+            pass,
+            change_num: 0,
+        },
+    );
     loop {
-        seen.insert(total, (repeat_count, 0));
-        for (change_num, change) in changes.iter().cloned().enumerate() {
-            total = total.checked_add(change).unwrap();
-            if let Some(_) = seen.insert(total, (repeat_count, change_num as i32 + 1)) {
-                return total;
+        for (change_idx, change) in changes.iter().enumerate() {
+            total = total.checked_add(change).ok_or(FrequencyOverflow)?;
+            let position = ChangePosition {
+                pass,
+                change_num: change_idx + 1,
+            };
+            if let Some(first_reached) = seen.insert(total.clone(), position) {
+                return Ok(FrequencyCycleReport {
+                    frequency: total,
+                    first_reached,
+                    repeated_at: position,
+                    steps: pass
+                        .checked_mul(changes.len())
+                        .and_then(|s| s.checked_add(change_idx + 1))
+                        .ok_or(CycleSearchError::StepCountOverflow)?,
+                });
+            }
+        }
+        pass = pass
+            .checked_add(1)
+            .ok_or(CycleSearchError::StepCountOverflow)?;
+    }
+}
+
+#[aoc(day1, part2)]
+pub fn day1_part2(input: &str) -> Result<i32, Day1Error> {
+    day1_part2_as(input)
+}
+
+/// Like `day1_part2`, but parses changes as and accumulates in `F`.
+///
+/// This never returns if no frequency ever repeats; see `first_repeated_frequency`.
+pub fn day1_part2_as<F: Frequency>(input: &str) -> Result<F, Day1Error<F::Err>> {
+    let changes = Day1EntryIterator::new(input).collect::<Result<Vec<F>, _>>()?;
+    Ok(frequency_cycle_report(&changes)?.frequency)
+}
+
+/// Finds the first repeated frequency of `changes` without simulating every pass over them.
+///
+/// Every frequency reached is some frequency `f` from the first pass plus a multiple of the net
+/// drift `d` of a whole pass, so `f` can only ever be revisited by another first-pass frequency
+/// congruent to it modulo `d`. Sorting first-pass frequencies by residue and value finds each
+/// one's nearest such successor, which makes this O(n log n). Returns `None` if no frequency ever
+/// repeats.
+pub fn frequency_cycle_report_analytic<F: Frequency>(
+    changes: &[F],
+) -> Result<Option<FrequencyCycleReport<F>>, CycleSearchError> {
+    let num_changes = changes.len();
+    let report = |frequency, first_reached, steps| FrequencyCycleReport {
+        frequency,
+        first_reached: ChangePosition::from_steps(first_reached, num_changes),
+        repeated_at: ChangePosition::from_steps(steps, num_changes),
+        steps,
+    };
+
+    let mut first_pass = Vec::with_capacity(num_changes);
+    let mut seen = HashMap::with_capacity(num_changes);
+    let mut total = F::zero();
+    for (step, change) in changes.iter().enumerate() {
+        if let Some(first_reached) = seen.insert(total.clone(), step) {
+            return Ok(Some(report(total, first_reached, step)));
+        }
+        let next_total = total.checked_add(change).ok_or(FrequencyOverflow)?;
+        first_pass.push((step, total));
+        total = next_total;
+    }
+
+    if num_changes == 0 {
+        return Ok(None);
+    }
+    let drift = total;
+    if drift == F::zero() {
+        return Ok(Some(report(drift, 0, num_changes)));
+    }
+
+    let mut first_pass = first_pass
+        .into_iter()
+        .map(|(step, frequency)| {
+            let residue = frequency
+                .checked_rem_euclid(&drift)
+                .ok_or(FrequencyOverflow)?;
+            Ok((residue, frequency, step))
+        })
+        .collect::<Result<Vec<_>, FrequencyOverflow>>()?;
+    let drift_is_negative = drift < F::zero();
+    first_pass.sort_by(|(r1, f1, _), (r2, f2, _)| {
+        r1.cmp(r2).then_with(|| match f1.cmp(f2) {
+            ordering if drift_is_negative => ordering.reverse(),
+            ordering => ordering,
+        })
+    });
+
+    let mut earliest_repeat: Option<(usize, usize, F)> = None;
+    for pair in first_pass.windows(2) {
+        let (start_residue, start_frequency, start_step) = &pair[0];
+        let (end_residue, end_frequency, end_step) = &pair[1];
+        if start_residue != end_residue {
+            continue;
+        }
+        let steps = start_frequency
+            .steps_to(end_frequency, &drift)
+            .and_then(|passes| passes.checked_mul(num_changes))
+            .and_then(|s| s.checked_add(*start_step))
+            .ok_or(CycleSearchError::StepCountOverflow)?;
+        match &earliest_repeat {
+            Some((earliest_steps, _, _)) if *earliest_steps <= steps => (),
+            _ => earliest_repeat = Some((steps, *end_step, end_frequency.clone())),
+        }
+    }
+    Ok(earliest_repeat
+        .map(|(steps, first_reached, frequency)| report(frequency, first_reached, steps)))
+}
+
+#[aoc(day1, part2, analytic)]
+pub fn day1_part2_analytic(input: &str) -> Result<i32, Day1Error> {
+    first_repeated_frequency(input)?.ok_or(Day1Error::NeverRepeats)
+}
+
+/// Parses `input` and finds its first repeated frequency, accumulating in `F`.
+pub fn first_repeated_frequency<F: Frequency>(input: &str) -> Result<Option<F>, Day1Error<F::Err>> {
+    let changes = Day1EntryIterator::new(input).collect::<Result<Vec<F>, _>>()?;
+    Ok(frequency_cycle_report_analytic(&changes)?.map(|report| report.frequency))
+}
+
+#[test]
+fn test_day1_entry_iterator_whitespace() {
+    let changes = Day1EntryIterator::new("+1\r\n  -2 \n+3\t\r\n")
+        .collect::<Result<Vec<i32>, _>>()
+        .unwrap();
+    assert_eq!(changes, [1, -2, 3]);
+}
+
+#[test]
+fn test_day1_entry_iterator_errors() {
+    use self::Day1ParseErrorKind::*;
+
+    let errors = Day1EntryIterator::<i32>::new("+1\n\n3\n+x\n# comment\n")
+        .filter_map(Result::err)
+        .map(|e| (e.line_number, e.line, e.kind))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        errors,
+        [
+            (2, "".to_owned(), EmptyLine),
+            (3, "3".to_owned(), MissingSign),
+            (
+                4,
+                "+x".to_owned(),
+                InvalidNumber("x".parse::<i32>().unwrap_err())
+            ),
+            (5, "# comment".to_owned(), MissingSign),
+        ]
+    );
+}
+
+#[test]
+fn test_day1_entry_iterator_lenient() {
+    let changes = Day1EntryIterator::lenient("# calibration run\n+1\n\n  \n-2\r\n# done\n")
+        .collect::<Result<Vec<i32>, _>>()
+        .unwrap();
+    assert_eq!(changes, [1, -2]);
+
+    let error = Day1EntryIterator::<i32>::lenient("+1\n\n-2\nbogus\n")
+        .find_map(Result::err)
+        .unwrap();
+    assert_eq!(error.line_number, 4);
+}
+
+#[cfg(test)]
+fn changes(input: &str) -> Vec<i32> {
+    Day1EntryIterator::new(input)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap()
+}
+
+#[test]
+fn test_day1_part2_hint() {
+    for (input, expected) in &[
+        ("+1\n-1\n", 0),
+        ("+3\n+3\n+4\n-2\n-4\n", 10),
+        ("-6\n+3\n+8\n+5\n-6\n", 5),
+        ("+7\n+7\n-2\n-7\n-4\n", 14),
+    ] {
+        assert_eq!(day1_part2(input), Ok(*expected), "input: {:?}", input);
+    }
+}
+
+#[test]
+fn test_frequency_cycle_report() {
+    assert_eq!(
+        frequency_cycle_report(&changes("+1\n-2\n+3\n+1\n")),
+        Ok(FrequencyCycleReport {
+            frequency: 2,
+            first_reached: ChangePosition {
+                pass: 0,
+                change_num: 3,
+            },
+            repeated_at: ChangePosition {
+                pass: 1,
+                change_num: 2,
+            },
+            steps: 6,
+        })
+    );
+
+    assert_eq!(
+        frequency_cycle_report(&changes("+1\n-1\n")),
+        Ok(FrequencyCycleReport {
+            frequency: 0,
+            first_reached: ChangePosition {
+                pass: 0,
+                change_num: 0,
+            },
+            repeated_at: ChangePosition {
+                pass: 0,
+                change_num: 2,
+            },
+            steps: 2,
+        })
+    );
+}
+
+#[test]
+fn test_day1_part2_analytic_hint() {
+    for (input, expected) in &[
+        ("+1\n-1\n", 0),
+        ("+3\n+3\n+4\n-2\n-4\n", 10),
+        ("-6\n+3\n+8\n+5\n-6\n", 5),
+        ("+7\n+7\n-2\n-7\n-4\n", 14),
+    ] {
+        assert_eq!(
+            day1_part2_analytic(input),
+            Ok(*expected),
+            "input: {:?}",
+            input
+        );
+    }
+    assert_eq!(day1_part2_analytic("+1\n"), Err(Day1Error::NeverRepeats));
+}
+
+#[test]
+fn test_day1_part2_analytic_never_repeats() {
+    assert_eq!(
+        frequency_cycle_report_analytic(&changes("+1\n+1\n")),
+        Ok(None)
+    );
+    assert_eq!(
+        frequency_cycle_report_analytic(&changes("-3\n+5\n")),
+        Ok(None)
+    );
+    assert_eq!(frequency_cycle_report_analytic::<i32>(&[]), Ok(None));
+
+    // Prefix sums -2100000001 and 2100000001 share a residue, but are further apart than an `i32`
+    // can represent.
+    let changes = [-2100000001, 2100000003, 2099999999, -2099999999];
+    assert_eq!(
+        frequency_cycle_report_analytic::<i32>(&changes),
+        Ok(Some(frequency_cycle_report(&changes).unwrap()))
+    );
+}
+
+#[test]
+fn test_day1_part2_analytic_matches_simulation() {
+    use std::collections::HashSet;
+
+    const VALUES: [i32; 7] = [-3, -2, -1, 0, 1, 2, 3];
+    for len in 1..=4u32 {
+        for combination in 0..VALUES.len().pow(len) {
+            let changes = (0..len)
+                .scan(combination, |c, _| {
+                    let value = VALUES[*c % VALUES.len()];
+                    *c /= VALUES.len();
+                    Some(value)
+                })
+                .collect::<Vec<_>>();
+            match frequency_cycle_report_analytic(&changes).unwrap() {
+                Some(report) => assert_eq!(
+                    report,
+                    frequency_cycle_report(&changes).unwrap(),
+                    "changes: {:?}",
+                    changes
+                ),
+                // Frequencies this small would repeat within a few passes if they ever did.
+                None => {
+                    let mut seen = HashSet::new();
+                    let mut total = 0;
+                    seen.insert(total);
+                    for change in changes.iter().cycle().take(100 * changes.len()) {
+                        total += change;
+                        assert!(seen.insert(total), "changes: {:?}", changes);
+                    }
+                }
             }
         }
-        repeat_count = repeat_count.checked_add(1).unwrap();
     }
 }
+
+#[test]
+fn test_day1_part2_analytic_answer() {
+    let input = include_str!("../input/2018/day1.txt");
+    assert_eq!(day1_part2_analytic(input), day1_part2(input));
+}
+
+#[test]
+fn test_day1_wide_frequencies() {
+    const INPUT: &str = "+2147483647\n+1\n-2147483648\n+4294967296\n";
+
+    assert_eq!(
+        Day1EntryIterator::<i32>::new(INPUT).nth(3).unwrap(),
+        Err(Day1ParseError {
+            line_number: 4,
+            line: "+4294967296".to_owned(),
+            kind: Day1ParseErrorKind::InvalidNumber("4294967296".parse::<i32>().unwrap_err()),
+        })
+    );
+    assert_eq!(
+        resulting_frequency(Day1EntryIterator::<i32>::new("+2147483647\n+1\n").map(Result::unwrap)),
+        Err(FrequencyOverflow)
+    );
+    assert_eq!(
+        resulting_frequency::<i64>(Day1EntryIterator::new(INPUT).map(Result::unwrap)),
+        Ok(4294967296)
+    );
+    assert_eq!(
+        resulting_frequency::<i128>(Day1EntryIterator::new(INPUT).map(Result::unwrap)),
+        Ok(4294967296)
+    );
+
+    assert_eq!(
+        first_repeated_frequency::<i32>("+2147483647\n+1\n-1\n"),
+        Err(Day1Error::Overflow(FrequencyOverflow))
+    );
+    assert_eq!(
+        first_repeated_frequency::<i64>("+2147483647\n+1\n-1\n"),
+        Ok(Some(2147483647))
+    );
+    assert_eq!(
+        day1_part1("+2147483647\n+1\n"),
+        Err(Day1Error::Overflow(FrequencyOverflow))
+    );
+    assert_eq!(day1_part1_as::<i64>("+2147483647\n+1\n"), Ok(2147483648));
+    assert_eq!(
+        day1_part2("+2147483647\n+1\n-1\n"),
+        Err(Day1Error::Overflow(FrequencyOverflow))
+    );
+    assert_eq!(
+        day1_part2_as::<i64>("+2147483647\n+1\n-1\n"),
+        Ok(2147483647)
+    );
+    assert_eq!(day1_part2_as::<i128>(INPUT), Ok(0));
+    assert_eq!(
+        day1_part2_as::<i32>(INPUT),
+        Err(Day1Error::Parse(Day1ParseError {
+            line_number: 4,
+            line: "+4294967296".to_owned(),
+            kind: Day1ParseErrorKind::InvalidNumber("4294967296".parse::<i32>().unwrap_err()),
+        }))
+    );
+
+    // 2^100 passes are needed to get from the first frequency to the second.
+    assert_eq!(
+        first_repeated_frequency::<i128>(
+            "-1267650600228229401496703205376\n+1267650600228229401496703205377\n"
+        ),
+        Err(Day1Error::StepCountOverflow)
+    );
+}
+
+#[cfg(feature = "bigint")]
+#[test]
+fn test_day1_bigint_frequencies() {
+    use num_bigint::BigInt;
+
+    let input = "+170141183460469231731687303715884105727\n".repeat(2)
+        + &"-170141183460469231731687303715884105727\n".repeat(2);
+    assert_eq!(
+        first_repeated_frequency::<i128>(&input),
+        Err(Day1Error::Overflow(FrequencyOverflow))
+    );
+    assert_eq!(
+        day1_part2_as::<BigInt>(&input),
+        first_repeated_frequency::<BigInt>(&input).map(Option::unwrap)
+    );
+    assert_eq!(
+        first_repeated_frequency::<BigInt>(&input),
+        Ok(Some(
+            "170141183460469231731687303715884105727".parse().unwrap()
+        ))
+    );
+}
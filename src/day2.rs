@@ -1,78 +1,413 @@
 use {
     aoc_runner_derive::aoc,
-    arrayvec::ArrayVec,
-    std::fmt::{Display, Formatter, Result as FmtResult},
+    std::{
+        collections::{BTreeMap, HashMap},
+        error::Error,
+        fmt::{Display, Formatter, Result as FmtResult},
+    },
 };
 
-#[aoc(day2, part1)]
-pub fn day2_part1(input: &str) -> u32 {
-    let mut count_had_two: u32 = 0;
-    let mut count_had_three: u32 = 0;
-    let mut letters = ArrayVec::<[(char, u32); 26]>::new();
-    for box_id in input.split('\n') {
-        'digit: for id_digit in box_id.chars() {
-            for (c, count) in letters.iter_mut() {
-                if *c == id_digit {
-                    *count += 1;
-                    continue 'digit;
-                }
-            }
-            letters.push((id_digit, 1));
+#[derive(Debug, Eq, PartialEq)]
+pub enum BoxIdParseError {
+    EmptyId {
+        line_number: usize,
+    },
+    InvalidCharacter {
+        line_number: usize,
+        /// 1-based index of the offending character within the line
+        column: usize,
+        c: char,
+    },
+    MismatchedLength {
+        line_number: usize,
+        expected: usize,
+        actual: usize,
+    },
+    /// Too many box IDs had repeated letters for the checksum to fit in a `u32`.
+    ChecksumOverflow,
+    /// No two box IDs differ in exactly one position.
+    NoPairFound,
+}
+
+impl Display for BoxIdParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        use self::BoxIdParseError::*;
+
+        match self {
+            EmptyId { line_number } => write!(f, "line {}: empty box ID", line_number),
+            InvalidCharacter {
+                line_number,
+                column,
+                c,
+            } => write!(
+                f,
+                "line {}, column {}: invalid character {:?} in box ID",
+                line_number, column, c
+            ),
+            MismatchedLength {
+                line_number,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "line {}: expected box ID of {} characters, got {}",
+                line_number, expected, actual
+            ),
+            ChecksumOverflow => write!(f, "checksum doesn't fit in a u32"),
+            NoPairFound => write!(f, "no two box IDs differ in exactly one position"),
         }
+    }
+}
 
-        let mut had_two = false;
-        let mut had_three = false;
-        for (_, count) in letters.drain(..) {
-            match count {
-                2 => had_two = true,
-                3 => had_three = true,
-                _ => (),
+impl Error for BoxIdParseError {}
+
+/// Splits `input` into one box ID per line, checking that every ID has the same number of
+/// characters. Trailing blank lines are ignored.
+pub fn parse_box_ids(input: &str) -> Result<Vec<&str>, BoxIdParseError> {
+    use self::BoxIdParseError::*;
+
+    let mut expected_len = None;
+    input
+        .trim_end()
+        .lines()
+        .enumerate()
+        .map(|(line_idx, box_id)| {
+            let line_number = line_idx + 1;
+            let mut len = 0;
+            for (char_idx, c) in box_id.chars().enumerate() {
+                if c.is_whitespace() || c.is_control() {
+                    return Err(InvalidCharacter {
+                        line_number,
+                        column: char_idx + 1,
+                        c,
+                    });
+                }
+                len += 1;
             }
+            match (len, expected_len) {
+                (0, _) => Err(EmptyId { line_number }),
+                (actual, Some(expected)) if actual != expected => Err(MismatchedLength {
+                    line_number,
+                    expected,
+                    actual,
+                }),
+                (actual, _) => {
+                    expected_len = Some(actual);
+                    Ok(box_id)
+                }
+            }
+        })
+        .collect()
+}
+
+/// The letters of a single box ID, grouped by how many times they occur in it.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct LetterMultiplicities {
+    /// Letters occurring exactly `k` times keyed by `k`, each in order of first occurrence
+    by_count: BTreeMap<usize, Vec<char>>,
+}
+
+impl LetterMultiplicities {
+    pub fn of(box_id: &str) -> Self {
+        let mut letters = Vec::<(char, usize)>::new();
+        let mut letter_indices = HashMap::new();
+        for id_digit in box_id.chars() {
+            let idx = *letter_indices.entry(id_digit).or_insert_with(|| {
+                letters.push((id_digit, 0));
+                letters.len() - 1
+            });
+            letters[idx].1 += 1;
         }
 
-        if had_two {
-            count_had_two += 1;
+        let mut by_count = BTreeMap::<usize, Vec<char>>::new();
+        for (letter, count) in letters {
+            by_count.entry(count).or_default().push(letter);
         }
-        if had_three {
-            count_had_three += 1;
+        Self { by_count }
+    }
+
+    /// The letters occurring exactly `count` times.
+    pub fn letters_occurring(&self, count: usize) -> &[char] {
+        self.by_count
+            .get(&count)
+            .map_or(&[], |letters| &letters[..])
+    }
+
+    /// Whether some letter occurs exactly `count` times.
+    pub fn has_multiplicity(&self, count: usize) -> bool {
+        self.by_count.contains_key(&count)
+    }
+
+    /// Every multiplicity that occurs along with its letters, in increasing order of
+    /// multiplicity.
+    pub fn iter<'a>(&'a self) -> impl Iterator<Item = (usize, &'a [char])> + 'a {
+        self.by_count
+            .iter()
+            .map(|(count, letters)| (*count, &letters[..]))
+    }
+}
+
+/// Parses `input` and computes the letter multiplicities of every box ID in it.
+pub fn letter_multiplicities(input: &str) -> Result<Vec<LetterMultiplicities>, BoxIdParseError> {
+    Ok(parse_box_ids(input)?
+        .into_iter()
+        .map(LetterMultiplicities::of)
+        .collect())
+}
+
+/// For each of `multiplicities`, counts the box IDs having some letter occurring exactly that many
+/// times, then multiplies those counts together. Returns `None` on overflow.
+pub fn checksum<'a, I>(box_ids: I, multiplicities: &[usize]) -> Option<u32>
+where
+    I: IntoIterator<Item = &'a LetterMultiplicities>,
+{
+    let mut counts = vec![0u32; multiplicities.len()];
+    for box_id in box_ids {
+        for (count, multiplicity) in counts.iter_mut().zip(multiplicities.iter()) {
+            if box_id.has_multiplicity(*multiplicity) {
+                *count = count.checked_add(1)?;
+            }
         }
     }
-    count_had_two.checked_mul(count_had_three).unwrap()
+    counts
+        .into_iter()
+        .try_fold(1u32, |product, count| product.checked_mul(count))
 }
 
-pub struct Part2Answer<'s> {
-    s1: &'s str,
-    s2: &'s str,
+#[aoc(day2, part1)]
+pub fn day2_part1(input: &str) -> Result<u32, BoxIdParseError> {
+    checksum(&letter_multiplicities(input)?, &[2, 3]).ok_or(BoxIdParseError::ChecksumOverflow)
 }
 
-impl<'s> Display for Part2Answer<'s> {
-    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
-        for c in self
-            .s1
+/// A pair of box IDs of the same length.
+///
+/// Displays as the characters both IDs have in common, in order, leaving out every position where
+/// they differ. The alternate form (`{:#}`) instead shows each differing position as `_`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Part2Answer {
+    s1: String,
+    s2: String,
+}
+
+impl Part2Answer {
+    pub fn ids(&self) -> (&str, &str) {
+        (&self.s1, &self.s2)
+    }
+
+    /// The number of positions at which the two IDs differ.
+    pub fn distance(&self) -> usize {
+        self.differing_positions().count()
+    }
+
+    /// The (character-based) positions at which the two IDs differ.
+    pub fn differing_positions<'a>(&'a self) -> impl Iterator<Item = usize> + 'a {
+        self.s1
             .chars()
             .zip(self.s2.chars())
-            .filter(|(c1, c2)| c1 == c2)
-            .map(|(c, _)| c)
-        {
-            write!(f, "{}", c)?;
+            .enumerate()
+            .filter(|(_, (c1, c2))| c1 != c2)
+            .map(|(idx, _)| idx)
+    }
+}
+
+impl Display for Part2Answer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        for (c1, c2) in self.s1.chars().zip(self.s2.chars()) {
+            if c1 == c2 {
+                write!(f, "{}", c1)?;
+            } else if f.alternate() {
+                write!(f, "_")?;
+            }
         }
         Ok(())
     }
 }
 
+/// Answers Hamming distance queries over a set of box IDs of the same length.
+pub struct BoxIdIndex<'s> {
+    ids: Vec<&'s str>,
+    id_len: usize,
+    /// The characters of every ID, laid out one ID after another
+    chars: Vec<char>,
+}
+
+impl<'s> BoxIdIndex<'s> {
+    /// Indexes `ids`.
+    ///
+    /// Panics if the IDs don't all have the same number of characters; use `parse` to check them
+    /// instead.
+    pub fn new<I: IntoIterator<Item = &'s str>>(ids: I) -> Self {
+        let ids = ids.into_iter().collect::<Vec<_>>();
+        let id_len = ids.first().map_or(0, |id| id.chars().count());
+        let mut chars = Vec::with_capacity(ids.len() * id_len);
+        for id in ids.iter() {
+            let len_before = chars.len();
+            chars.extend(id.chars());
+            assert_eq!(
+                chars.len() - len_before,
+                id_len,
+                "box ID {:?} is not {} characters long",
+                id,
+                id_len
+            );
+        }
+        Self { ids, id_len, chars }
+    }
+
+    pub fn parse(input: &'s str) -> Result<Self, BoxIdParseError> {
+        parse_box_ids(input).map(Self::new)
+    }
+
+    pub fn ids(&self) -> &[&'s str] {
+        &self.ids
+    }
+
+    fn chars_of(&self, idx: usize) -> &[char] {
+        &self.chars[idx * self.id_len..(idx + 1) * self.id_len]
+    }
+
+    /// The Hamming distance between two IDs, or `None` if it exceeds `max_distance`.
+    fn distance_within(c1: &[char], c2: &[char], max_distance: usize) -> Option<usize> {
+        let mut distance = 0;
+        for (c1, c2) in c1.iter().zip(c2.iter()) {
+            if c1 != c2 {
+                distance += 1;
+                if distance > max_distance {
+                    return None;
+                }
+            }
+        }
+        Some(distance)
+    }
+
+    fn pair(&self, idx1: usize, idx2: usize) -> Part2Answer {
+        Part2Answer {
+            s1: self.ids[idx1].to_string(),
+            s2: self.ids[idx2].to_string(),
+        }
+    }
+
+    /// Index pairs of all IDs within `max_distance` of each other, in no particular order.
+    ///
+    /// IDs within distance `d` of each other must agree completely on at least one of any `d + 1`
+    /// blocks their characters are split into, so only IDs sharing a block are compared.
+    fn index_pairs_within(&self, max_distance: usize) -> Vec<(usize, usize)> {
+        let num_ids = self.ids.len();
+        if max_distance >= self.id_len {
+            return (0..num_ids)
+                .flat_map(|i| (i + 1..num_ids).map(move |j| (i, j)))
+                .collect();
+        }
+
+        let num_blocks = max_distance + 1;
+        let block_bounds = |block: usize| {
+            (
+                block * self.id_len / num_blocks,
+                (block + 1) * self.id_len / num_blocks,
+            )
+        };
+        let mut pairs = Vec::new();
+        let mut buckets = HashMap::<&[char], Vec<usize>>::new();
+        for block in 0..num_blocks {
+            let (start, end) = block_bounds(block);
+            buckets.clear();
+            for idx in 0..num_ids {
+                buckets
+                    .entry(&self.chars_of(idx)[start..end])
+                    .or_default()
+                    .push(idx);
+            }
+            for bucket in buckets.values() {
+                for (bucket_idx, &i) in bucket.iter().enumerate() {
+                    for &j in bucket[bucket_idx + 1..].iter() {
+                        let (c1, c2) = (self.chars_of(i), self.chars_of(j));
+                        // Only report each pair for the first block the two IDs share
+                        let shared_earlier_block = (0..block).any(|earlier| {
+                            let (start, end) = block_bounds(earlier);
+                            c1[start..end] == c2[start..end]
+                        });
+                        if !shared_earlier_block
+                            && Self::distance_within(c1, c2, max_distance).is_some()
+                        {
+                            pairs.push((i, j));
+                        }
+                    }
+                }
+            }
+        }
+        pairs
+    }
+
+    /// All pairs of IDs differing in at most `max_distance` positions, ordered by their positions
+    /// in the index.
+    pub fn pairs_within(&self, max_distance: usize) -> Vec<Part2Answer> {
+        let mut pairs = self.index_pairs_within(max_distance);
+        pairs.sort();
+        pairs.into_iter().map(|(i, j)| self.pair(i, j)).collect()
+    }
+
+    /// The indexed ID closest to `id` (other than `id` itself) along with its distance from
+    /// `id`. Ties go to the ID indexed first.
+    pub fn nearest_neighbour(&self, id: &str) -> Option<(&'s str, usize)> {
+        let query = id.chars().collect::<Vec<_>>();
+        if query.len() != self.id_len {
+            return None;
+        }
+        let mut nearest: Option<(&'s str, usize)> = None;
+        for (idx, other) in self.ids.iter().enumerate() {
+            if *other == id {
+                continue;
+            }
+            let max_distance = nearest.map_or(self.id_len, |(_, d)| d.saturating_sub(1));
+            if let Some(distance) = Self::distance_within(&query, self.chars_of(idx), max_distance)
+            {
+                nearest = Some((other, distance));
+                if distance <= 1 {
+                    break;
+                }
+            }
+        }
+        nearest
+    }
+
+    /// Groups IDs into clusters where every ID is connected to every other through a chain of IDs
+    /// differing in at most `max_distance` positions. IDs without any such neighbour form their
+    /// own clusters. Clusters and their members are ordered by their positions in the index.
+    pub fn clusters(&self, max_distance: usize) -> Vec<Vec<&'s str>> {
+        fn root(parents: &mut [usize], mut idx: usize) -> usize {
+            while parents[idx] != idx {
+                parents[idx] = parents[parents[idx]];
+                idx = parents[idx];
+            }
+            idx
+        }
+
+        let mut parents = (0..self.ids.len()).collect::<Vec<_>>();
+        for (i, j) in self.index_pairs_within(max_distance) {
+            let (root_i, root_j) = (root(&mut parents, i), root(&mut parents, j));
+            parents[root_i.max(root_j)] = root_i.min(root_j);
+        }
+
+        let mut clusters = Vec::<Vec<&'s str>>::new();
+        let mut cluster_of_root = HashMap::new();
+        for (idx, id) in self.ids.iter().enumerate() {
+            let cluster_idx = *cluster_of_root
+                .entry(root(&mut parents, idx))
+                .or_insert_with(|| {
+                    clusters.push(Vec::new());
+                    clusters.len() - 1
+                });
+            clusters[cluster_idx].push(id);
+        }
+        clusters
+    }
+}
+
 #[aoc(day2, part2)]
-pub fn day2_part2(input: &str) -> &'static str {
-    let box_ids = input
-        .split('\n')
-        .take_while(|s| !s.is_empty())
-        .collect::<ArrayVec<[&str; 250]>>();
-    for box_id in box_ids.iter() {
-        assert_eq!(box_id.len(), 26);
-    }
-    assert!(box_ids.is_full());
-
-    let num_box_ids = box_ids.len();
-    for (box_id_idx, box_id) in box_ids[..num_box_ids - 1].iter().enumerate() {
+pub fn day2_part2(input: &str) -> Result<Part2Answer, BoxIdParseError> {
+    let box_ids = parse_box_ids(input)?;
+
+    for (box_id_idx, box_id) in box_ids.iter().enumerate() {
         for other_box_id in box_ids[box_id_idx + 1..].iter() {
             let num_differences = box_id
                 .chars()
@@ -80,18 +415,292 @@ pub fn day2_part2(input: &str) -> &'static str {
                 .filter(|(c1, c2)| c1 != c2)
                 .count();
             if num_differences == 1 {
-                let answer = Part2Answer {
-                    s1: box_id,
-                    s2: other_box_id,
-                };
-
-                // FIXME: #1: We COULD just return this theoretically, but `aoc`'s implementation
-                // prevents using a return value with a lifetime for some reason
-                // return answer;
-                println!("answer: {}", answer);
-                return "";
+                return Ok(Part2Answer {
+                    s1: box_id.to_string(),
+                    s2: other_box_id.to_string(),
+                });
+            }
+        }
+    }
+    Err(BoxIdParseError::NoPairFound)
+}
+
+/// Finds two IDs differing in exactly one position by hashing every ID once per position with
+/// that position left out. That's O(n·k) hash map operations on keys of length O(k), so O(n·k²)
+/// time in total, instead of the O(n²·k) of comparing every pair of IDs.
+#[aoc(day2, part2, masked_hash)]
+pub fn day2_part2_masked_hash(input: &str) -> Result<Part2Answer, BoxIdParseError> {
+    let box_ids = parse_box_ids(input)?;
+    let id_len = box_ids.first().map_or(0, |id| id.chars().count());
+
+    // Byte offset of the character currently being masked out in each ID
+    let mut masked_offsets = vec![0; box_ids.len()];
+    let mut ids_without_masked = HashMap::with_capacity(box_ids.len());
+    for _ in 0..id_len {
+        ids_without_masked.clear();
+        for (box_id, masked_offset) in box_ids.iter().zip(masked_offsets.iter_mut()) {
+            // `parse_box_ids` made sure that every ID is `id_len` characters long.
+            let masked = box_id[*masked_offset..].chars().next().unwrap();
+            let (before, rest) = box_id.split_at(*masked_offset);
+            let after = &rest[masked.len_utf8()..];
+            *masked_offset += masked.len_utf8();
+
+            match ids_without_masked.insert((before, after), (*box_id, masked)) {
+                Some((other_box_id, other_masked)) if other_masked != masked => {
+                    return Ok(Part2Answer {
+                        s1: other_box_id.to_string(),
+                        s2: box_id.to_string(),
+                    });
+                }
+                _ => (),
+            }
+        }
+    }
+    Err(BoxIdParseError::NoPairFound)
+}
+
+#[cfg(test)]
+const INPUT: &'static str = include_str!("../input/2018/day2.txt");
+
+#[test]
+fn test_day2_part1_hint() {
+    const HINT_INPUT: &'static str = r#"abcdef
+bababc
+abbcde
+abcccd
+aabcdd
+abcdee
+ababab
+"#;
+    assert_eq!(day2_part1(HINT_INPUT), Ok(12));
+}
+
+#[test]
+fn test_letter_multiplicities() {
+    let profile = LetterMultiplicities::of("bababc");
+    assert_eq!(profile.letters_occurring(1), ['c']);
+    assert_eq!(profile.letters_occurring(2), ['a']);
+    assert_eq!(profile.letters_occurring(3), ['b']);
+    assert_eq!(profile.letters_occurring(4), []);
+    assert!(!profile.has_multiplicity(4));
+
+    let profile = LetterMultiplicities::of("zzyyxxxxw");
+    assert_eq!(
+        profile.iter().collect::<Vec<_>>(),
+        [(1, &['w'][..]), (2, &['z', 'y'][..]), (4, &['x'][..])]
+    );
+
+    let profiles =
+        letter_multiplicities("abcdef\nbababc\nabbcde\nabcccd\naabcdd\nabcdee\nababab\n").unwrap();
+    assert_eq!(checksum(&profiles, &[2]), Some(4));
+    assert_eq!(checksum(&profiles, &[3]), Some(3));
+    assert_eq!(checksum(&profiles, &[1, 2, 3]), Some(6 * 4 * 3));
+    assert_eq!(checksum(&profiles, &[]), Some(1));
+}
+
+#[test]
+fn test_day2_part1_answer() {
+    assert_eq!(day2_part1(INPUT), Ok(6200));
+}
+
+#[test]
+fn test_day2_part2_hint() {
+    const HINT_INPUT: &'static str = r#"abcde
+fghij
+klmno
+pqrst
+fguij
+axcye
+wvxyz
+"#;
+    assert_eq!(day2_part2(HINT_INPUT).unwrap().to_string(), "fgij");
+    assert_eq!(
+        day2_part2_masked_hash(HINT_INPUT).unwrap().to_string(),
+        "fgij"
+    );
+}
+
+#[test]
+fn test_day2_part2_answer() {
+    assert_eq!(
+        day2_part2(INPUT).unwrap().to_string(),
+        "xpysnnkqrbuhefmcajodplyzw"
+    );
+    assert_eq!(
+        day2_part2_masked_hash(INPUT).unwrap().to_string(),
+        "xpysnnkqrbuhefmcajodplyzw"
+    );
+}
+
+#[test]
+fn test_day2_part2_masked_hash_duplicates() {
+    assert_eq!(
+        day2_part2_masked_hash("abcd\nabcd\nwxyz\nabce\n")
+            .unwrap()
+            .to_string(),
+        "abc"
+    );
+}
+
+#[test]
+fn test_day2_part2_masked_hash_large_inventory() {
+    let mut seed: u32 = 2018;
+    let mut random_letter = || {
+        seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+        (b'a' + (seed >> 16) as u8 % 26) as char
+    };
+    let mut input = String::new();
+    for id_num in 0..200_000 {
+        if id_num == 1234 {
+            input.push_str("haystackneed\n");
+        }
+        for _ in 0..12 {
+            input.push(random_letter());
+        }
+        input.push('\n');
+    }
+    input.push_str("haystackneXd\n");
+    assert_eq!(
+        day2_part2_masked_hash(&input).unwrap().to_string(),
+        "haystackned"
+    );
+}
+
+#[test]
+fn test_day2_unicode_box_ids() {
+    const UNICODE_INPUT: &'static str = "ÄÖÜß\r\nßÖÜÄ\r\nÄÖxß\r\nÄÄÄy\r\n\r\n";
+    assert_eq!(day2_part1(UNICODE_INPUT), Ok(0));
+    assert_eq!(day2_part2(UNICODE_INPUT).unwrap().to_string(), "ÄÖß");
+    assert_eq!(
+        day2_part2_masked_hash(UNICODE_INPUT).unwrap().to_string(),
+        "ÄÖß"
+    );
+}
+
+#[test]
+fn test_parse_box_ids_errors() {
+    use self::BoxIdParseError::*;
+
+    assert_eq!(
+        parse_box_ids("abc\nab\n"),
+        Err(MismatchedLength {
+            line_number: 2,
+            expected: 3,
+            actual: 2,
+        })
+    );
+    assert_eq!(
+        parse_box_ids("abc\n\nabc\n"),
+        Err(EmptyId { line_number: 2 })
+    );
+    assert_eq!(
+        parse_box_ids("abc\na c\n"),
+        Err(InvalidCharacter {
+            line_number: 2,
+            column: 2,
+            c: ' ',
+        })
+    );
+
+    // The solvers report these rather than panicking.
+    assert_eq!(
+        day2_part1("abc\nab\n"),
+        Err(MismatchedLength {
+            line_number: 2,
+            expected: 3,
+            actual: 2,
+        })
+    );
+    assert_eq!(day2_part2("abc\n\nabc\n"), Err(EmptyId { line_number: 2 }));
+    assert_eq!(day2_part2("abc\nxyz\nabc\n"), Err(NoPairFound));
+    assert_eq!(day2_part2(""), Err(NoPairFound));
+    assert_eq!(day2_part2_masked_hash("abc\nxyz\nabc\n"), Err(NoPairFound));
+    assert_eq!(day2_part2_masked_hash(""), Err(NoPairFound));
+    assert_eq!(
+        day2_part2_masked_hash("abc\na c\n"),
+        Err(InvalidCharacter {
+            line_number: 2,
+            column: 2,
+            c: ' ',
+        })
+    );
+    assert_eq!(
+        NoPairFound.to_string(),
+        "no two box IDs differ in exactly one position"
+    );
+}
+
+#[test]
+fn test_part2_answer_multiple_differences() {
+    let answer = BoxIdIndex::new(vec!["abcde", "axcye"]).pair(0, 1);
+    assert_eq!(answer.distance(), 2);
+    assert_eq!(answer.differing_positions().collect::<Vec<_>>(), [1, 3]);
+    assert_eq!(answer.to_string(), "ace");
+    assert_eq!(format!("{:#}", answer), "a_c_e");
+}
+
+#[test]
+fn test_box_id_index_hint() {
+    let index = BoxIdIndex::parse("abcde\nfghij\nklmno\npqrst\nfguij\naxcye\nwvxyz\n").unwrap();
+
+    let ids_within = |d| {
+        index
+            .pairs_within(d)
+            .iter()
+            .map(|p| (p.ids().0.to_owned(), p.ids().1.to_owned()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(ids_within(0), []);
+    assert_eq!(ids_within(1), [("fghij".to_owned(), "fguij".to_owned())]);
+    assert_eq!(
+        ids_within(2),
+        [
+            ("abcde".to_owned(), "axcye".to_owned()),
+            ("fghij".to_owned(), "fguij".to_owned()),
+        ]
+    );
+    assert_eq!(index.pairs_within(5).len(), 7 * 6 / 2);
+
+    assert_eq!(index.nearest_neighbour("fghij"), Some(("fguij", 1)));
+    assert_eq!(index.nearest_neighbour("abcdz"), Some(("abcde", 1)));
+    assert_eq!(index.nearest_neighbour("axcde"), Some(("abcde", 1)));
+    assert_eq!(index.nearest_neighbour("abc"), None);
+
+    assert_eq!(
+        index.clusters(2),
+        vec![
+            vec!["abcde", "axcye"],
+            vec!["fghij", "fguij"],
+            vec!["klmno"],
+            vec!["pqrst"],
+            vec!["wvxyz"],
+        ]
+    );
+}
+
+#[test]
+fn test_box_id_index_pairs_match_brute_force() {
+    let index = BoxIdIndex::parse(INPUT).unwrap();
+    for max_distance in 0..=4 {
+        let mut expected = Vec::new();
+        for (i, id1) in index.ids().iter().enumerate() {
+            for id2 in index.ids()[i + 1..].iter() {
+                let distance = id1
+                    .chars()
+                    .zip(id2.chars())
+                    .filter(|(c1, c2)| c1 != c2)
+                    .count();
+                if distance <= max_distance {
+                    expected.push((*id1, *id2));
+                }
             }
         }
+        let actual = index.pairs_within(max_distance);
+        assert_eq!(
+            actual.iter().map(Part2Answer::ids).collect::<Vec<_>>(),
+            expected,
+            "max distance: {}",
+            max_distance
+        );
     }
-    panic!("No answer found");
 }
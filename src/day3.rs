@@ -1,33 +1,174 @@
 use {
+    crate::grid::{CellGrid, GrowOnlyGrid, SparseGrid},
     aoc_runner_derive::aoc,
-    re_parse::{Error as ReParseError, ReParse, Regex},
-    serde_derive::Deserialize,
+    csv::{ReaderBuilder, Trim},
+    serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer},
+    serde_derive::{Deserialize, Serialize},
     std::{
-        cmp::max,
-        mem::replace,
-        ops::{Index, IndexMut},
-        slice::Iter as SliceIter,
-        str::{FromStr, Split},
+        cmp::{max, min},
+        collections::HashMap,
+        error::Error,
+        fmt::{Debug, Display, Formatter, Result as FmtResult},
+        fs::File,
+        io::{self, BufWriter, Write},
+        iter::Enumerate,
+        ops::Range,
+        path::Path,
+        ptr,
+        str::{FromStr, Lines},
     },
 };
 
-struct ClaimIterator<'s> {
-    input: Split<'s, char>,
+/// An axis-aligned rectangle of cells, spanning `left..right` horizontally and `top..bottom`
+/// vertically.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Rect {
+    left: usize,
+    top: usize,
+    right: usize,
+    bottom: usize,
 }
 
-impl<'s> ClaimIterator<'s> {
-    pub fn new(input: &'s str) -> Self {
-        ClaimIterator {
-            input: input.split('\n'),
+impl Rect {
+    /// Returns `None` if `right` is left of `left` or `bottom` is above `top`.
+    pub fn new(left: usize, top: usize, right: usize, bottom: usize) -> Option<Self> {
+        if left <= right && top <= bottom {
+            Some(Self {
+                left,
+                top,
+                right,
+                bottom,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Returns `None` if the rectangle would extend past `usize::MAX`.
+    pub fn from_origin_and_size(
+        left: usize,
+        top: usize,
+        width: usize,
+        height: usize,
+    ) -> Option<Self> {
+        Self::new(
+            left,
+            top,
+            left.checked_add(width)?,
+            top.checked_add(height)?,
+        )
+    }
+
+    pub fn left(&self) -> usize {
+        self.left
+    }
+
+    pub fn top(&self) -> usize {
+        self.top
+    }
+
+    pub fn right(&self) -> usize {
+        self.right
+    }
+
+    pub fn bottom(&self) -> usize {
+        self.bottom
+    }
+
+    pub fn width(&self) -> usize {
+        self.right - self.left
+    }
+
+    pub fn height(&self) -> usize {
+        self.bottom - self.top
+    }
+
+    /// Widened so that even a rectangle spanning every `usize` coordinate fits.
+    pub fn area(&self) -> u128 {
+        self.width() as u128 * self.height() as u128
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.left == self.right || self.top == self.bottom
+    }
+
+    pub fn contains_point(&self, (x, y): (usize, usize)) -> bool {
+        self.left <= x && x < self.right && self.top <= y && y < self.bottom
+    }
+
+    /// Whether any cell is covered by both rectangles.
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.intersection(other).is_some()
+    }
+
+    /// The cells covered by both rectangles, if any.
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        let left = max(self.left, other.left);
+        let top = max(self.top, other.top);
+        let right = min(self.right, other.right);
+        let bottom = min(self.bottom, other.bottom);
+        if left < right && top < bottom {
+            Some(Self {
+                left,
+                top,
+                right,
+                bottom,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// The smallest rectangle containing both rectangles.
+    pub fn union_bounds(&self, other: &Self) -> Self {
+        Self {
+            left: min(self.left, other.left),
+            top: min(self.top, other.top),
+            right: max(self.right, other.right),
+            bottom: max(self.bottom, other.bottom),
         }
     }
 }
 
-#[derive(Debug, Deserialize, ReParse)]
-#[re_parse(
-    regex = r#"#(?P<id>\d{1,4}) @ (?P<left>\d{1,3}),(?P<top>\d{1,3}): (?P<width>\d{1,2})x(?P<height>\d{1,2})"#
-)]
-struct RawClaim {
+#[derive(Clone, Debug)]
+pub struct Claim {
+    id: usize,
+    rect: Rect,
+}
+
+impl Claim {
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    pub fn rect(&self) -> &Rect {
+        &self.rect
+    }
+
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.rect.intersects(&other.rect)
+    }
+}
+
+/// Writes the claim in the same `#id @ left,top: widthxheight` syntax it's parsed from.
+impl Display for Claim {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let Self { id, rect } = self;
+        write!(
+            f,
+            "#{} @ {},{}: {}x{}",
+            id,
+            rect.left(),
+            rect.top(),
+            rect.width(),
+            rect.height()
+        )
+    }
+}
+
+/// A claim as it's laid out in structured formats like JSON or CSV, before it's validated.
+#[derive(Debug, Deserialize, Serialize)]
+struct ClaimRecord {
     id: usize,
     left: usize,
     top: usize,
@@ -35,36 +176,43 @@ struct RawClaim {
     height: usize,
 }
 
-#[derive(Clone, Debug)]
-struct Claim {
-    id: usize,
-    left: usize,
-    top: usize,
-    right: usize,
-    bottom: usize,
+impl ClaimRecord {
+    fn to_claim(&self) -> Result<Claim, ClaimParseErrorKind> {
+        let &Self {
+            id,
+            left,
+            top,
+            width,
+            height,
+        } = self;
+        if width == 0 || height == 0 {
+            return Err(ClaimParseErrorKind::InvalidDimensions { width, height });
+        }
+        let rect = Rect::from_origin_and_size(left, top, width, height)
+            .ok_or(ClaimParseErrorKind::OutOfBounds)?;
+        Ok(Claim { id, rect })
+    }
 }
 
-impl Claim {
-    // FIXME: This is actually wrong, and I've just compensated by making `intersects` inclusive.
-    // There should be no need to call this twice!
-    fn contains_edge_of(&self, other: &Self) -> (bool, bool) {
-        let intersects_horizontally = {
-            let bottom_in_horizontal_band = self.bottom > other.top && self.bottom <= other.bottom;
-            let top_in_horizontal_band = self.top >= other.top && self.top < other.bottom;
-            bottom_in_horizontal_band || top_in_horizontal_band
-        };
-        let intersects_vertically = {
-            let left_in_vertical_band = self.left >= other.left && self.left < other.right;
-            let right_in_vertical_band = self.right > other.left && self.right <= other.right;
-            left_in_vertical_band || right_in_vertical_band
-        };
-        (intersects_horizontally, intersects_vertically)
+impl Serialize for Claim {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let Self { id, rect } = self;
+        ClaimRecord {
+            id: *id,
+            left: rect.left(),
+            top: rect.top(),
+            width: rect.width(),
+            height: rect.height(),
+        }
+        .serialize(serializer)
     }
+}
 
-    pub fn intersects(&self, other: &Self) -> bool {
-        let (self_contains_horiz, self_contains_vert) = self.contains_edge_of(other);
-        let (other_contains_horiz, other_contains_vert) = other.contains_edge_of(self);
-        (self_contains_horiz || other_contains_horiz) && (self_contains_vert || other_contains_vert)
+impl<'de> Deserialize<'de> for Claim {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        ClaimRecord::deserialize(deserializer)?
+            .to_claim()
+            .map_err(D::Error::custom)
     }
 }
 
@@ -133,287 +281,1837 @@ fn test_intersection() {
     ));
 }
 
-#[derive(Debug)]
-enum ClaimParseError {
-    ParseFailed(ReParseError),
-    InvalidDimensions(usize, usize),
+#[test]
+fn test_rect_bounds() {
+    assert_eq!(Rect::new(2, 0, 1, 1), None);
+    assert_eq!(Rect::new(0, 2, 1, 1), None);
+    assert_eq!(
+        Rect::from_origin_and_size(usize::max_value(), 0, 1, 1),
+        None
+    );
+
+    let everything = Rect::new(0, 0, usize::max_value(), usize::max_value()).unwrap();
+    let max = usize::max_value() as u128;
+    assert_eq!(everything.area(), max * max);
+    assert_eq!(Rect::new(3, 3, 3, 9).unwrap().area(), 0);
 }
 
-impl FromStr for Claim {
-    type Err = ClaimParseError;
+#[cfg(test)]
+mod rect_properties {
+    use {super::Rect, proptest::prelude::*, std::collections::HashSet};
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        use self::ClaimParseError::*;
+    fn cells(rect: &Rect) -> HashSet<(usize, usize)> {
+        (rect.top()..rect.bottom())
+            .flat_map(|y| (rect.left()..rect.right()).map(move |x| (x, y)))
+            .collect()
+    }
+
+    fn rect() -> impl Strategy<Value = Rect> {
+        (0..10usize, 0..10usize, 0..6usize, 0..6usize).prop_map(|(left, top, width, height)| {
+            Rect::from_origin_and_size(left, top, width, height).unwrap()
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn test_overlapping_area_matches_cell_model(rects in prop::collection::vec(rect(), 0..8)) {
+            let mut cell_counts = std::collections::HashMap::new();
+            for rect in rects.iter() {
+                for cell in cells(rect) {
+                    *cell_counts.entry(cell).or_insert(0) += 1;
+                }
+            }
+            prop_assert_eq!(
+                super::overlapping_area(&rects),
+                cell_counts.values().filter(|&&c| c > 1).count() as u128
+            );
+        }
+
+        #[test]
+        fn test_rect_matches_cell_model(r1 in rect(), r2 in rect(), x in 0..16usize, y in 0..16usize) {
+            let (cells1, cells2) = (cells(&r1), cells(&r2));
+            let shared = cells1.intersection(&cells2).cloned().collect::<HashSet<_>>();
+
+            prop_assert_eq!(r1.area(), cells1.len() as u128);
+            prop_assert_eq!(r1.is_empty(), cells1.is_empty());
+            prop_assert_eq!(r1.contains_point((x, y)), cells1.contains(&(x, y)));
+            prop_assert_eq!(r1.intersects(&r2), !shared.is_empty());
+            prop_assert_eq!(r1.intersects(&r2), r2.intersects(&r1));
+            prop_assert_eq!(r1.intersection(&r2).map_or_else(HashSet::new, |r| cells(&r)), shared);
+
+            let bounds = r1.union_bounds(&r2);
+            prop_assert!(cells(&bounds).is_superset(&cells1));
+            prop_assert!(cells(&bounds).is_superset(&cells2));
+            if !r1.is_empty() && !r2.is_empty() {
+                let all = cells1.union(&cells2).cloned().collect::<Vec<_>>();
+                prop_assert_eq!(bounds.left(), all.iter().map(|c| c.0).min().unwrap());
+                prop_assert_eq!(bounds.top(), all.iter().map(|c| c.1).min().unwrap());
+                prop_assert_eq!(bounds.right(), all.iter().map(|c| c.0).max().unwrap() + 1);
+                prop_assert_eq!(bounds.bottom(), all.iter().map(|c| c.1).max().unwrap() + 1);
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ClaimParseErrorKind {
+    EmptyLine,
+    /// A particular character was expected, like the `@` between a claim's ID and its position
+    Expected(char),
+    ExpectedNumber,
+    NumberTooLarge,
+    TrailingCharacters,
+    InvalidDimensions {
+        width: usize,
+        height: usize,
+    },
+    /// The claim would extend past the largest representable coordinate.
+    OutOfBounds,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ClaimParseError {
+    /// 1-based line number of the offending line
+    pub line_number: usize,
+    /// 1-based column of the first offending character, counted in characters
+    pub column: usize,
+    pub kind: ClaimParseErrorKind,
+}
+
+impl Display for ClaimParseErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        use self::ClaimParseErrorKind::*;
+
+        match self {
+            EmptyLine => write!(f, "unexpected empty line"),
+            Expected(c) => write!(f, "expected {:?}", c),
+            ExpectedNumber => write!(f, "expected a number"),
+            NumberTooLarge => write!(f, "number is too large"),
+            TrailingCharacters => write!(f, "unexpected characters after claim"),
+            InvalidDimensions { width, height } => {
+                write!(f, "claim has an invalid size of {}x{}", width, height)
+            }
+            OutOfBounds => write!(f, "claim extends past the largest possible coordinate"),
+        }
+    }
+}
+
+impl Display for ClaimParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line_number, self.column, self.kind
+        )
+    }
+}
+
+impl Error for ClaimParseError {}
+
+/// Reads the parts of a claim in `#id @ left,top: widthxheight` syntax off of a line, allowing
+/// whitespace between them.
+struct ClaimCursor<'s> {
+    line: &'s str,
+    /// Byte offset of the next character to read
+    pos: usize,
+}
+
+impl<'s> ClaimCursor<'s> {
+    fn skip_whitespace(&mut self) {
+        let rest = &self.line[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), (usize, ClaimParseErrorKind)> {
+        self.skip_whitespace();
+        if self.line[self.pos..].starts_with(expected) {
+            self.pos += expected.len_utf8();
+            Ok(())
+        } else {
+            Err((self.pos, ClaimParseErrorKind::Expected(expected)))
+        }
+    }
+
+    fn number(&mut self) -> Result<usize, (usize, ClaimParseErrorKind)> {
+        use self::ClaimParseErrorKind::*;
+
+        self.skip_whitespace();
+        let start = self.pos;
+        let digits = self.line[start..]
+            .bytes()
+            .take_while(u8::is_ascii_digit)
+            .count();
+        if digits == 0 {
+            return Err((start, ExpectedNumber));
+        }
+        self.pos += digits;
+        self.line[start..self.pos]
+            .bytes()
+            .try_fold(0usize, |n, digit| {
+                n.checked_mul(10)?.checked_add(usize::from(digit - b'0'))
+            })
+            .ok_or((start, NumberTooLarge))
+    }
+
+    fn claim(&mut self) -> Result<Claim, (usize, ClaimParseErrorKind)> {
+        use self::ClaimParseErrorKind::*;
+
+        self.skip_whitespace();
+        if self.pos == self.line.len() {
+            return Err((self.pos, EmptyLine));
+        }
+        self.expect('#')?;
+        let id = self.number()?;
+        self.expect('@')?;
+        let left = self.number()?;
+        self.expect(',')?;
+        let top = self.number()?;
+        self.expect(':')?;
+        let size_start = self.pos;
+        let width = self.number()?;
+        self.expect('x')?;
+        let height = self.number()?;
+        self.skip_whitespace();
+        if self.pos != self.line.len() {
+            return Err((self.pos, TrailingCharacters));
+        }
 
-        let RawClaim {
+        ClaimRecord {
             id,
             left,
             top,
             width,
             height,
-        } = RawClaim::from_str(s).map_err(ParseFailed)?;
-
-        if width == 0 || height == 0 {
-            return Err(InvalidDimensions(width, height));
         }
+        .to_claim()
+        .map_err(|kind| (size_start, kind))
+    }
+}
 
-        Ok(Self {
-            id,
-            left,
-            top,
-            right: left.checked_add(width).unwrap(),
-            bottom: top.checked_add(height).unwrap(),
+/// Parses a single line holding a claim in the AoC syntax.
+fn parse_claim(line_number: usize, line: &str) -> Result<Claim, ClaimParseError> {
+    ClaimCursor { line, pos: 0 }
+        .claim()
+        .map_err(|(pos, kind)| ClaimParseError {
+            line_number,
+            column: line[..pos].chars().count() + 1,
+            kind,
         })
-    }
 }
 
-impl<'s> Iterator for ClaimIterator<'s> {
-    type Item = Claim;
+impl FromStr for Claim {
+    type Err = ClaimParseError;
 
-    fn next(&mut self) -> Option<Self::Item> {
-        match self.input.next()? {
-            "" => None,
-            other => Some(other.parse().unwrap()),
-        }
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_claim(1, s)
     }
 }
 
-struct GrowOnlyGrid<T> {
-    inner: Vec<T>,
-    len_x: usize,
-    len_y: usize,
+/// Parses claims from input with one claim per line, without allocating.
+pub struct ClaimIterator<'s> {
+    lines: Enumerate<Lines<'s>>,
+    lenient: bool,
+    failed: bool,
 }
 
-impl<T> GrowOnlyGrid<T> {
-    pub fn new_with<F: FnMut() -> T>(x: usize, y: usize, mut f: F) -> Self {
+impl<'s> ClaimIterator<'s> {
+    /// Parses every line of `input` as a claim, stopping after the first one that isn't.
+    pub fn new(input: &'s str) -> Self {
         Self {
-            inner: {
-                let len = x.checked_mul(y).unwrap();
-                let mut inner = Vec::with_capacity(len);
-                // OPT: Use the soon-to-be-stable `resize_with` instead.
-                while inner.len() < len {
-                    inner.push(f());
-                }
-                inner
-            },
-            len_x: x,
-            len_y: y,
+            lines: input.lines().enumerate(),
+            lenient: false,
+            failed: false,
         }
     }
 
-    pub fn grow_with<F: FnMut() -> T>(&mut self, x: usize, y: usize, f: F)
-    where
-        T: Default,
-    {
-        let old_len_x = self.len_x;
-        let old_len_y = self.len_y;
-        let old = replace(
-            self,
-            Self::new_with(max(x, old_len_x), max(y, old_len_y), f),
-        );
-
-        let mut old_values = old.inner.into_iter();
-        for y in 0..old_len_y {
-            // OPT:  We could probably just copy slices here directly
-            for x in 0..old_len_x {
-                let idx = unsafe { self.index_from_coords_unchecked(x, y) };
-                self.inner[idx] = old_values.next().unwrap();
-            }
+    /// Like `new`, but skips blank lines and keeps going after lines that fail to parse.
+    pub fn lenient(input: &'s str) -> Self {
+        Self {
+            lenient: true,
+            ..Self::new(input)
         }
     }
+}
 
-    pub fn dimensions(&self) -> (usize, usize) {
-        (self.len_x, self.len_y)
-    }
+impl<'s> Iterator for ClaimIterator<'s> {
+    type Item = Result<Claim, ClaimParseError>;
 
-    fn index_from_coords(&self, x: usize, y: usize) -> usize {
-        if x >= self.len_x || y >= self.len_y {
-            panic!(
-                "coordinates {:?} exceed current dimensions of {:?}",
-                (x, y),
-                self.dimensions()
-            );
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        loop {
+            let (line_idx, line) = self.lines.next()?;
+            if self.lenient && line.trim().is_empty() {
+                continue;
+            }
+            let claim = parse_claim(line_idx + 1, line);
+            self.failed = claim.is_err() && !self.lenient;
+            break Some(claim);
         }
-        unsafe { self.index_from_coords_unchecked(x, y) }
     }
+}
 
-    unsafe fn index_from_coords_unchecked(&self, x: usize, y: usize) -> usize {
-        y * self.len_x + x
+/// The formats claims can be read from.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ClaimFormat {
+    /// One claim per line in the `#id @ left,top: widthxheight` syntax of the puzzle input
+    Aoc,
+    /// An array of objects with `id`, `left`, `top`, `width` and `height` fields
+    Json,
+    /// A header row naming `id`, `left`, `top`, `width` and `height` columns, then a row per claim
+    Csv,
+}
+
+impl ClaimFormat {
+    /// Parses every claim in `input`, failing on the first invalid one.
+    pub fn parse(self, input: &str) -> Result<Vec<Claim>, ClaimInputError> {
+        Ok(match self {
+            ClaimFormat::Aoc => ClaimIterator::new(input).collect::<Result<_, _>>()?,
+            ClaimFormat::Json => serde_json::from_str(input)?,
+            ClaimFormat::Csv => ReaderBuilder::new()
+                .trim(Trim::All)
+                .from_reader(input.as_bytes())
+                .deserialize()
+                .collect::<Result<_, _>>()?,
+        })
     }
 }
 
-impl<T> Index<(usize, usize)> for GrowOnlyGrid<T> {
-    type Output = T;
+#[derive(Debug)]
+pub enum ClaimInputError {
+    Aoc(ClaimParseError),
+    Json(serde_json::Error),
+    Csv(csv::Error),
+}
 
-    fn index(&self, (x, y): (usize, usize)) -> &Self::Output {
-        let idx = self.index_from_coords(x, y);
-        &self.inner[idx]
+impl Display for ClaimInputError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            ClaimInputError::Aoc(e) => write!(f, "{}", e),
+            ClaimInputError::Json(e) => write!(f, "invalid JSON claims: {}", e),
+            ClaimInputError::Csv(e) => write!(f, "invalid CSV claims: {}", e),
+        }
     }
 }
 
-impl<T> IndexMut<(usize, usize)> for GrowOnlyGrid<T> {
-    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut Self::Output {
-        let idx = self.index_from_coords(x, y);
-        &mut self.inner[idx]
+impl From<ClaimParseError> for ClaimInputError {
+    fn from(e: ClaimParseError) -> Self {
+        ClaimInputError::Aoc(e)
     }
 }
 
-impl<T> GrowOnlyGrid<T> {
-    pub fn iter_flat(&self) -> SliceIter<T> {
-        self.inner[..].iter()
+impl From<serde_json::Error> for ClaimInputError {
+    fn from(e: serde_json::Error) -> Self {
+        ClaimInputError::Json(e)
     }
 }
 
-#[aoc(day3, part1)]
-pub fn day3_part1(input: &str) -> usize {
-    let mut grid = GrowOnlyGrid::<u8>::new_with(1000, 1000, Default::default);
-    for claim in ClaimIterator::new(input) {
-        let Claim {
-            id: _,
-            left,
-            top,
-            right,
-            bottom,
-        } = claim;
-
-        grid.grow_with(
-            right.checked_add(1).unwrap(),
-            bottom.checked_add(1).unwrap(),
-            Default::default,
-        );
-
-        for y in top..bottom {
-            for x in left..right {
-                let blarg = &mut grid[(x, y)];
-                *blarg = blarg.checked_add(1).unwrap();
-            }
-        }
+impl From<csv::Error> for ClaimInputError {
+    fn from(e: csv::Error) -> Self {
+        ClaimInputError::Csv(e)
     }
+}
 
-    grid.iter_flat().filter(|x| x > &&1).count()
+/// Parses every claim in `input`, failing on the first invalid one.
+fn parse_claims(input: &str) -> Result<Vec<Claim>, ClaimParseError> {
+    ClaimIterator::new(input).collect()
+}
+
+/// Like `day3_part1_sweep_line`, but reads claims in the given `format`.
+pub fn day3_part1_from(input: &str, format: ClaimFormat) -> Result<u128, ClaimInputError> {
+    let claims = format.parse(input)?;
+    Ok(overlapping_area(claims.iter().map(|c| &c.rect)))
+}
+
+/// Like `day3_part2_spatial_index`, but reads claims in the given `format`.
+pub fn day3_part2_from(
+    input: &str,
+    format: ClaimFormat,
+) -> Result<UncontestedClaims, ClaimInputError> {
+    Ok(uncontested_claims(&format.parse(input)?))
 }
 
 #[cfg(test)]
-const INPUT: &'static str = include_str!("../input/2018/day3.txt");
-#[cfg(test)]
-const HINT_INPUT: &'static str = r#"#1 @ 1,3: 4x4
-#2 @ 3,1: 4x4
-#3 @ 5,5: 2x2
-"#;
-#[cfg(test)]
-const HINT_EXPECTED_PART1_OUTPUT: usize = 4;
-#[cfg(test)]
-const HINT_EXPECTED_PART2_OUTPUT: usize = 3;
+const HINT_INPUT_JSON: &str = r#"[
+    {"id": 1, "left": 1, "top": 3, "width": 4, "height": 4},
+    {"id": 2, "left": 3, "top": 1, "width": 4, "height": 4},
+    {"id": 3, "left": 5, "top": 5, "width": 2, "height": 2}
+]"#;
 #[cfg(test)]
-const EXPECTED_PART2_OUTPUT: usize = 603;
+const HINT_INPUT_CSV: &str = "id, left, top, width, height
+1, 1, 3, 4, 4
+2, 3, 1, 4, 4
+3, 5, 5, 2, 2
+";
 
 #[test]
-fn test_day3_part1_hint() {
-    assert_eq!(day3_part1(HINT_INPUT), HINT_EXPECTED_PART1_OUTPUT);
-}
+fn test_claim_formats() {
+    let aoc = parse_claims(HINT_INPUT).unwrap();
+    let display = |claims: &[Claim]| {
+        claims
+            .iter()
+            .map(|c| format!("{}\n", c))
+            .collect::<String>()
+    };
+    assert_eq!(display(&aoc), HINT_INPUT);
+    assert_eq!(display(&parse_claims(INPUT).unwrap()), INPUT);
 
-#[aoc(day3, part2, square_iteration)]
-pub fn day3_part2_square_iteration(input: &str) -> usize {
-    // OPT: Use ArrayVec for even more performance? Depends on max size.
-    // OR OPT: Pre-allocating might be beneficial here, not sure how `size_hint` works for char
-    // splits.
-    let mut claims = ClaimIterator::new(input)
-        .map(|c| (c, true))
-        .collect::<Vec<_>>();
+    for &(format, input) in &[
+        (ClaimFormat::Aoc, HINT_INPUT),
+        (ClaimFormat::Json, HINT_INPUT_JSON),
+        (ClaimFormat::Csv, HINT_INPUT_CSV),
+    ] {
+        assert_eq!(display(&format.parse(input).unwrap()), HINT_INPUT);
+    }
 
-    for i in 0..claims.len() {
-        for j in i + 1..claims.len() {
-            if claims[i].0.intersects(&claims[j].0) {
-                (&mut claims[i]).1 = false;
-                (&mut claims[j]).1 = false;
-            }
-        }
+    let json = serde_json::to_string(&aoc).unwrap();
+    let from_json: Vec<Claim> = serde_json::from_str(&json).unwrap();
+    assert_eq!(display(&from_json), HINT_INPUT);
+
+    let error = ClaimFormat::Json
+        .parse(r#"[{"id": 1, "left": 1, "top": 3, "width": 0, "height": 4}]"#)
+        .unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .contains("claim has an invalid size of 0x4"),
+        "{}",
+        error
+    );
+    match ClaimFormat::Csv.parse("id,left,top,width,height\n1,1,3,4\n") {
+        Err(ClaimInputError::Csv(_)) => (),
+        other => panic!("expected a CSV error, got {:?}", other),
     }
 
-    let uncontested = claims
-        .into_iter()
-        .filter_map(|(c, uncontested)| if uncontested { Some(c) } else { None })
-        .collect::<Vec<_>>();
-    if uncontested.len() != 1 {
-        panic!("Expected single remaining claim, got {:?}", uncontested);
+    // AoC input that doesn't start with a claim still gets reported as such.
+    for (input, kind) in &[
+        ("\n#1 @ 1,3: 4x4\n", ClaimParseErrorKind::EmptyLine),
+        (
+            "\u{feff}#1 @ 1,3: 4x4\n",
+            ClaimParseErrorKind::Expected('#'),
+        ),
+        ("1 @ 1,3: 4x4\n", ClaimParseErrorKind::Expected('#')),
+    ] {
+        match ClaimFormat::Aoc.parse(input) {
+            Err(ClaimInputError::Aoc(e)) => {
+                assert_eq!((e.line_number, e.column, &e.kind), (1, 1, kind))
+            }
+            other => panic!("expected an AoC claim error, got {:?}", other),
+        }
     }
-    uncontested[0].id
 }
 
 #[test]
-fn test_day3_part2_square_iteration_hint() {
-    assert_eq!(
-        day3_part2_square_iteration(HINT_INPUT),
-        HINT_EXPECTED_PART2_OUTPUT
-    );
+fn test_day3_structured_input() {
+    for &(format, input) in &[
+        (ClaimFormat::Aoc, HINT_INPUT),
+        (ClaimFormat::Json, HINT_INPUT_JSON),
+        (ClaimFormat::Csv, HINT_INPUT_CSV),
+    ] {
+        assert_eq!(
+            day3_part1_from(input, format).unwrap(),
+            HINT_EXPECTED_PART1_OUTPUT as u128
+        );
+        assert_eq!(
+            day3_part2_from(input, format).unwrap(),
+            UncontestedClaims(vec![HINT_EXPECTED_PART2_OUTPUT])
+        );
+    }
+    assert!(day3_part1_from(HINT_INPUT_JSON, ClaimFormat::Csv).is_err());
 }
 
 #[test]
-fn test_day3_part2_square_iteration_answer() {
-    assert_eq!(day3_part2_square_iteration(INPUT), EXPECTED_PART2_OUTPUT);
-}
-
-#[aoc(day3, part2, grid_again)]
-pub fn day3_part2_grid_again(input: &str) -> usize {
-    let mut grid = GrowOnlyGrid::<u8>::new_with(1000, 1000, Default::default);
-    let claims = ClaimIterator::new(input).collect::<Vec<_>>();
-    for Claim {
-        id: _,
-        left,
-        top,
-        right,
-        bottom,
-    } in claims.iter()
-    {
-        grid.grow_with(
-            right.checked_add(1).unwrap(),
-            bottom.checked_add(1).unwrap(),
-            Default::default,
-        );
+fn test_claim_parser() {
+    use self::ClaimParseErrorKind::*;
 
-        for y in *top..*bottom {
-            for x in *left..*right {
-                *(&mut grid[(x, y)]) += 1;
-            }
-        }
-    }
+    let claims =
+        parse_claims("  #1 @ 1,3: 4x4 \r\n#2@3 , 1:4 x 4\r\n#123456789012 @ 9876543210,0: 1x1")
+            .unwrap();
+    assert_eq!(
+        claims
+            .iter()
+            .map(|c| (c.id(), *c.rect()))
+            .collect::<Vec<_>>(),
+        [
+            (1, Rect::new(1, 3, 5, 7).unwrap()),
+            (2, Rect::new(3, 1, 7, 5).unwrap()),
+            (
+                123_456_789_012,
+                Rect::new(9_876_543_210, 0, 9_876_543_211, 1).unwrap()
+            ),
+        ]
+    );
 
-    let uncontested = claims
-        .into_iter()
-        .filter(
-            |Claim {
-                 left,
-                 top,
-                 bottom,
-                 right,
-                 ..
-             }| {
-                for y in *top..*bottom {
-                    for x in *left..*right {
-                        let count = grid[(x, y)];
-                        assert!(count != 0);
-                        if count > 1 {
-                            return false;
-                        }
-                    }
-                }
-                true
+    let error = |s: &str| s.parse::<Claim>().map(|_| ()).unwrap_err();
+    let kind_and_column = |s: &str| {
+        let e = error(s);
+        (e.kind, e.column)
+    };
+    assert_eq!(kind_and_column(""), (EmptyLine, 1));
+    assert_eq!(kind_and_column("1 @ 1,3: 4x4"), (Expected('#'), 1));
+    assert_eq!(kind_and_column("#1 @ 1;3: 4x4"), (Expected(','), 7));
+    assert_eq!(kind_and_column("#1 @ 1,3: x4"), (ExpectedNumber, 11));
+    assert_eq!(kind_and_column("#1 @ 1,3: 4x4 x"), (TrailingCharacters, 15));
+    assert_eq!(
+        kind_and_column("#1 @ 1,3: 0x4"),
+        (
+            InvalidDimensions {
+                width: 0,
+                height: 4
             },
+            10
         )
-        .collect::<Vec<_>>();
-    assert_eq!(uncontested.len(), 1);
-    uncontested[0].id
+    );
+    assert_eq!(
+        kind_and_column("#99999999999999999999999 @ 1,3: 4x4"),
+        (NumberTooLarge, 2)
+    );
+    let max = format!("#1 @ {},0: 1x1", usize::max_value());
+    assert_eq!(kind_and_column(&max).0, OutOfBounds);
+    assert_eq!(kind_and_column("#1 @ ünï,3: 4x4"), (ExpectedNumber, 6));
+    assert_eq!(kind_and_column("#1 @ 1,3: 4×4"), (Expected('x'), 12));
+    assert_eq!(
+        error("#1 @ 1,3: 4x4 x").to_string(),
+        "line 1, column 15: unexpected characters after claim"
+    );
+
+    // The solvers report invalid claims rather than panicking.
+    let invalid = "#1 @ 1,3: 4x4\n#2 @ 3,1 4x4\n";
+    let expected = ClaimParseError {
+        line_number: 2,
+        column: 10,
+        kind: Expected(':'),
+    };
+    assert_eq!(day3_part1(invalid), Err(expected.clone()));
+    assert_eq!(day3_part1_sparse_grid(invalid), Err(expected.clone()));
+    assert_eq!(day3_part1_sweep_line(invalid), Err(expected.clone()));
+    assert_eq!(day3_part2_square_iteration(invalid), Err(expected.clone()));
+    assert_eq!(day3_part2_spatial_index(invalid), Err(expected.clone()));
+    assert_eq!(day3_part2_grid_again(invalid), Err(expected.clone()));
+    assert_eq!(day3_part2_sparse_grid(invalid), Err(expected));
 }
 
 #[test]
-fn test_day3_part2_grid_again_hint() {
+fn test_claim_iterator_modes() {
+    let input = "#1 @ 1,3: 4x4\n\n#2 @ 3,1: 4\n  \n#3 @ 5,5: 2x2\n";
+    let strict = ClaimIterator::new(input)
+        .map(|c| c.map(|c| c.id()).map_err(|e| (e.line_number, e.kind)))
+        .collect::<Vec<_>>();
+    assert_eq!(strict, [Ok(1), Err((2, ClaimParseErrorKind::EmptyLine))]);
+
+    let lenient = ClaimIterator::lenient(input)
+        .map(|c| {
+            c.map(|c| c.id())
+                .map_err(|e| (e.line_number, e.column, e.kind))
+        })
+        .collect::<Vec<_>>();
     assert_eq!(
-        day3_part2_grid_again(HINT_INPUT),
-        HINT_EXPECTED_PART2_OUTPUT
+        lenient,
+        [
+            Ok(1),
+            Err((3, 12, ClaimParseErrorKind::Expected('x'))),
+            Ok(3)
+        ]
     );
 }
 
-#[test]
-fn test_day3_part2_grid_again_answer() {
-    assert_eq!(day3_part2_grid_again(INPUT), EXPECTED_PART2_OUTPUT);
+/// How many claims cover a square inch of fabric.
+///
+/// Plain unsigned integers refuse to count past their maximum, while `Saturating` ones stick at it,
+/// which still tells contested square inches apart from uncontested ones.
+pub trait ClaimCount: Copy + Debug + Default + Ord {
+    const ONE: Self;
+
+    /// Counts one more claim, or returns `None` if that doesn't fit.
+    fn add_claim(self) -> Option<Self>;
+
+    fn to_u64(self) -> u64;
+}
+
+/// A count that stays at its maximum instead of overflowing.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Saturating<C>(pub C);
+
+macro_rules! impl_claim_count {
+    ($($t: ty),*) => {
+        $(
+            impl ClaimCount for $t {
+                const ONE: Self = 1;
+
+                fn add_claim(self) -> Option<Self> {
+                    self.checked_add(1)
+                }
+
+                fn to_u64(self) -> u64 {
+                    self as u64
+                }
+            }
+
+            impl ClaimCount for Saturating<$t> {
+                const ONE: Self = Saturating(1);
+
+                fn add_claim(self) -> Option<Self> {
+                    Some(Saturating(self.0.saturating_add(1)))
+                }
+
+                fn to_u64(self) -> u64 {
+                    self.0 as u64
+                }
+            }
+        )*
+    };
+}
+
+impl_claim_count!(u8, u16, u32, u64, usize);
+
+/// More claims cover a square inch of fabric than the chosen `ClaimCount` can count.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ClaimCountOverflow {
+    pub x: usize,
+    pub y: usize,
+}
+
+impl Display for ClaimCountOverflow {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(
+            f,
+            "too many claims cover the square inch at ({}, {}) to count",
+            self.x, self.y
+        )
+    }
+}
+
+/// Counts how many claims cover each square inch of fabric in `grid`, which is assumed to start out
+/// with every count at zero.
+pub fn count_claims_in<'c, C, G, I>(grid: &mut G, claims: I) -> Result<(), ClaimCountOverflow>
+where
+    C: ClaimCount,
+    G: CellGrid<C>,
+    I: IntoIterator<Item = &'c Claim>,
+{
+    for Claim { rect, .. } in claims {
+        grid.grow_to(
+            rect.right().checked_add(1).unwrap(),
+            rect.bottom().checked_add(1).unwrap(),
+        );
+
+        for y in rect.top()..rect.bottom() {
+            for x in rect.left()..rect.right() {
+                let blarg = &mut grid[(x, y)];
+                *blarg = blarg.add_claim().ok_or(ClaimCountOverflow { x, y })?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Counts the square inches of fabric within two or more of `claims`, using `grid` to count claims
+/// with `C`. Whether counts saturate, widen or fail with `ClaimCountOverflow` is up to `C`.
+pub fn day3_part1_with_grid<C, G>(
+    claims: &[Claim],
+    grid: &mut G,
+) -> Result<usize, ClaimCountOverflow>
+where
+    C: ClaimCount,
+    G: CellGrid<C>,
+{
+    count_claims_in(grid, claims)?;
+    Ok(grid.stored_cells().filter(|&&x| x > C::ONE).count())
+}
+
+#[aoc(day3, part1)]
+pub fn day3_part1(input: &str) -> Result<usize, ClaimParseError> {
+    let claims = parse_claims(input)?;
+    let mut grid = GrowOnlyGrid::<Saturating<u8>>::new_with(1000, 1000, Default::default);
+    // `Saturating` counts never overflow.
+    Ok(day3_part1_with_grid(&claims, &mut grid).unwrap())
+}
+
+/// Like `day3_part1`, but only allocates the parts of the fabric that claims cover.
+#[aoc(day3, part1, sparse_grid)]
+pub fn day3_part1_sparse_grid(input: &str) -> Result<usize, ClaimParseError> {
+    let claims = parse_claims(input)?;
+    // `Saturating` counts never overflow.
+    Ok(day3_part1_with_grid(&claims, &mut SparseGrid::new(Saturating(0u8))).unwrap())
+}
+
+/// What a `SegmentTree` tracks for each node, worked out from how many ranges cover all of the
+/// node's segments without covering its parent's, and from what its children track.
+trait SegmentSummary: Copy + Default {
+    /// Summarizes a node spanning `len` coordinates and covered `count` times, whose children are
+    /// summarized by `children` unless it's a leaf.
+    fn combine(count: usize, len: usize, children: Option<(Self, Self)>) -> Self;
+}
+
+/// A segment tree over compressed coordinate segments, keeping a summary of every node up to date
+/// as a multiset of ranges changes.
+struct SegmentTree<S> {
+    /// Boundaries of the segments, in increasing order
+    bounds: Vec<usize>,
+    /// Number of ranges covering all of each node's segments without covering its parent's
+    cover_counts: Vec<usize>,
+    summaries: Vec<S>,
+}
+
+impl<S: SegmentSummary> SegmentTree<S> {
+    pub fn new(bounds: Vec<usize>) -> Self {
+        let num_nodes = 4 * bounds.len().max(1);
+        Self {
+            bounds,
+            cover_counts: vec![0; num_nodes],
+            summaries: vec![S::default(); num_nodes],
+        }
+    }
+
+    /// Adds or removes the range `start..end`, whose ends must be in `bounds`. Only ranges that
+    /// were added before may be removed.
+    pub fn update(&mut self, start: usize, end: usize, add: bool) {
+        let segment = |bound| self.bounds.binary_search(&bound).unwrap();
+        let (start, end) = (segment(start), segment(end));
+        if start < end {
+            let num_segments = self.bounds.len() - 1;
+            self.update_node(1, 0, num_segments, start, end, add);
+        }
+    }
+
+    /// The summary of every segment.
+    pub fn root(&self) -> S {
+        self.summaries[1]
+    }
+
+    fn update_node(
+        &mut self,
+        node: usize,
+        node_start: usize,
+        node_end: usize,
+        start: usize,
+        end: usize,
+        add: bool,
+    ) {
+        if end <= node_start || node_end <= start {
+            return;
+        }
+        if start <= node_start && node_end <= end {
+            if add {
+                self.cover_counts[node] += 1;
+            } else {
+                self.cover_counts[node] -= 1;
+            }
+        } else {
+            let mid = (node_start + node_end) / 2;
+            self.update_node(2 * node, node_start, mid, start, end, add);
+            self.update_node(2 * node + 1, mid, node_end, start, end, add);
+        }
+
+        let children = if node_end - node_start == 1 {
+            None
+        } else {
+            Some((self.summaries[2 * node], self.summaries[2 * node + 1]))
+        };
+        let len = self.bounds[node_end] - self.bounds[node_start];
+        self.summaries[node] = S::combine(self.cover_counts[node], len, children);
+    }
+}
+
+/// How much of a node's segments is covered at least once and at least twice.
+#[derive(Clone, Copy, Debug, Default)]
+struct Coverage {
+    once: usize,
+    twice: usize,
+}
+
+impl SegmentSummary for Coverage {
+    fn combine(count: usize, len: usize, children: Option<(Self, Self)>) -> Self {
+        let (left, right) = children.unwrap_or_default();
+        match count {
+            0 => Self {
+                once: left.once + right.once,
+                twice: left.twice + right.twice,
+            },
+            1 => Self {
+                once: len,
+                twice: left.once + right.once,
+            },
+            _ => Self {
+                once: len,
+                twice: len,
+            },
+        }
+    }
+}
+
+type CoverageTree = SegmentTree<Coverage>;
+
+impl CoverageTree {
+    pub fn covered_once(&self) -> usize {
+        self.root().once
+    }
+
+    pub fn covered_twice(&self) -> usize {
+        self.root().twice
+    }
+}
+
+/// The greatest cover count of any segment below a node, counting only the node and its
+/// descendants.
+#[derive(Clone, Copy, Debug, Default)]
+struct MaxDepth(usize);
+
+impl SegmentSummary for MaxDepth {
+    fn combine(count: usize, _len: usize, children: Option<(Self, Self)>) -> Self {
+        let (left, right) = children.unwrap_or_default();
+        MaxDepth(count + max(left.0, right.0))
+    }
+}
+
+type DepthTree = SegmentTree<MaxDepth>;
+
+impl DepthTree {
+    /// The greatest depth of any segment along with the start of the first segment having it.
+    pub fn max_depth(&self) -> (usize, usize) {
+        let (mut node, mut node_start, mut node_end) = (1, 0, self.bounds.len() - 1);
+        while node_end - node_start > 1 {
+            let mid = (node_start + node_end) / 2;
+            if self.summaries[2 * node].0 >= self.summaries[2 * node + 1].0 {
+                node *= 2;
+                node_end = mid;
+            } else {
+                node = 2 * node + 1;
+                node_start = mid;
+            }
+        }
+        (self.root().0, self.bounds[node_start])
+    }
+}
+
+/// The left and right edges of every non-empty rectangle in `rects`, ordered by position with
+/// right edges (`-1`) before left edges (`1`) at the same position, along with the rectangles'
+/// distinct top and bottom coordinates.
+fn sweep_edges<'r, I: IntoIterator<Item = &'r Rect>>(
+    rects: I,
+) -> (Vec<(usize, i32, &'r Rect)>, Vec<usize>) {
+    let mut edges = Vec::new();
+    let mut ys = Vec::new();
+    for rect in rects.into_iter().filter(|r| !r.is_empty()) {
+        edges.push((rect.left(), 1, rect));
+        edges.push((rect.right(), -1, rect));
+        ys.push(rect.top());
+        ys.push(rect.bottom());
+    }
+    ys.sort();
+    ys.dedup();
+    edges.sort_by_key(|&(x, delta, _)| (x, delta));
+    (edges, ys)
+}
+
+/// Like `Rect::area`, the total is widened so that it can't overflow: it's never more than the
+/// area of the rectangles' bounds.
+fn swept_area<'r, I, F>(rects: I, covered: F) -> u128
+where
+    I: IntoIterator<Item = &'r Rect>,
+    F: Fn(&CoverageTree) -> usize,
+{
+    let (edges, ys) = sweep_edges(rects);
+    let mut coverage = CoverageTree::new(ys);
+    let mut area: u128 = 0;
+    let mut last_x = edges.first().map_or(0, |&(x, _, _)| x);
+    for (x, delta, rect) in edges {
+        area += (x - last_x) as u128 * covered(&coverage) as u128;
+        coverage.update(rect.top(), rect.bottom(), delta > 0);
+        last_x = x;
+    }
+    area
+}
+
+/// The area covered by at least two of `rects`.
+///
+/// This sweeps a vertical line across the rectangles' left and right edges while a segment tree
+/// over their distinct top and bottom coordinates tracks how much of the line is covered twice, so
+/// it runs in O(n log n) regardless of how far apart the rectangles are.
+pub fn overlapping_area<'r, I: IntoIterator<Item = &'r Rect>>(rects: I) -> u128 {
+    swept_area(rects, CoverageTree::covered_twice)
+}
+
+/// The area covered by at least one of `rects`.
+pub fn union_area<'r, I: IntoIterator<Item = &'r Rect>>(rects: I) -> u128 {
+    swept_area(rects, CoverageTree::covered_once)
+}
+
+/// The greatest number of `rects` covering a single cell, along with the leftmost (and then
+/// topmost) such cell. Returns `None` if `rects` cover no cells at all.
+pub fn max_overlap_depth<'r, I: IntoIterator<Item = &'r Rect>>(
+    rects: I,
+) -> Option<(usize, (usize, usize))> {
+    let (edges, ys) = sweep_edges(rects);
+    let mut depths = DepthTree::new(ys);
+    let mut deepest = None;
+    let mut edges = edges.into_iter().peekable();
+    while let Some((x, delta, rect)) = edges.next() {
+        depths.update(rect.top(), rect.bottom(), delta > 0);
+        if edges.peek().map_or(true, |&(next_x, _, _)| next_x != x) {
+            let (depth, y) = depths.max_depth();
+            if depth > deepest.map_or(0, |(d, _)| d) {
+                deepest = Some((depth, (x, y)));
+            }
+        }
+    }
+    deepest
+}
+
+#[aoc(day3, part1, sweep_line)]
+pub fn day3_part1_sweep_line(input: &str) -> Result<u128, ClaimParseError> {
+    let claims = parse_claims(input)?;
+    Ok(overlapping_area(claims.iter().map(|c| &c.rect)))
+}
+
+#[cfg(test)]
+const INPUT: &'static str = include_str!("../input/2018/day3.txt");
+#[cfg(test)]
+const HINT_INPUT: &'static str = r#"#1 @ 1,3: 4x4
+#2 @ 3,1: 4x4
+#3 @ 5,5: 2x2
+"#;
+#[cfg(test)]
+const HINT_EXPECTED_PART1_OUTPUT: usize = 4;
+#[cfg(test)]
+const EXPECTED_PART1_OUTPUT: usize = 112378;
+#[cfg(test)]
+const HINT_EXPECTED_PART2_OUTPUT: usize = 3;
+#[cfg(test)]
+const EXPECTED_PART2_OUTPUT: usize = 603;
+
+#[test]
+fn test_day3_part1_hint() {
+    assert_eq!(day3_part1(HINT_INPUT).unwrap(), HINT_EXPECTED_PART1_OUTPUT);
+}
+
+#[test]
+fn test_day3_part1_answer() {
+    assert_eq!(day3_part1(INPUT).unwrap(), EXPECTED_PART1_OUTPUT);
+}
+
+#[test]
+fn test_day3_part1_sparse_grid_hint() {
+    assert_eq!(
+        day3_part1_sparse_grid(HINT_INPUT).unwrap(),
+        HINT_EXPECTED_PART1_OUTPUT
+    );
+}
+
+#[test]
+fn test_day3_part1_sparse_grid_answer() {
+    assert_eq!(
+        day3_part1_sparse_grid(INPUT).unwrap(),
+        EXPECTED_PART1_OUTPUT
+    );
+}
+
+#[test]
+fn test_day3_part1_sweep_line_hint() {
+    assert_eq!(
+        day3_part1_sweep_line(HINT_INPUT).unwrap(),
+        HINT_EXPECTED_PART1_OUTPUT as u128
+    );
+}
+
+#[test]
+fn test_day3_part1_sweep_line_answer() {
+    assert_eq!(
+        day3_part1_sweep_line(INPUT).unwrap(),
+        EXPECTED_PART1_OUTPUT as u128
+    );
+}
+
+#[test]
+fn test_overlapping_area_huge_coordinates() {
+    const BILLION: usize = 1_000_000_000;
+    let rects = [
+        Rect::from_origin_and_size(0, 0, 2 * BILLION, 2 * BILLION).unwrap(),
+        Rect::from_origin_and_size(BILLION, BILLION, 2 * BILLION, 2 * BILLION).unwrap(),
+        Rect::from_origin_and_size(3 * BILLION, 3 * BILLION, 5, 5).unwrap(),
+        Rect::from_origin_and_size(3 * BILLION + 4, 3 * BILLION + 4, 5, 5).unwrap(),
+    ];
+    assert_eq!(overlapping_area(&rects), (BILLION * BILLION + 1) as u128);
+    assert_eq!(overlapping_area(&rects[2..]), 1);
+    assert_eq!(overlapping_area(&[]), 0);
+
+    // Overlaps too big to count in a `usize`
+    let side = 5 * BILLION;
+    let huge = Rect::from_origin_and_size(0, 0, side, side).unwrap();
+    assert_eq!(overlapping_area(&[huge, huge]), huge.area());
+    assert!(huge.area() > usize::max_value() as u128);
+    let everything = Rect::new(0, 0, usize::max_value(), usize::max_value()).unwrap();
+    assert_eq!(
+        overlapping_area(&[everything, everything, huge]),
+        everything.area()
+    );
+    assert_eq!(union_area(&[everything, huge]), everything.area());
+}
+
+/// IDs of the claims not overlapping any other claim, displayed comma-separated.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UncontestedClaims(pub Vec<usize>);
+
+impl Display for UncontestedClaims {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        for (idx, id) in self.0.iter().enumerate() {
+            if idx != 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{}", id)?;
+        }
+        Ok(())
+    }
+}
+
+/// Compares every pair of claims, taking O(n²) time. This is the baseline that the other part 2
+/// solvers are measured against.
+#[aoc(day3, part2, square_iteration)]
+pub fn day3_part2_square_iteration(input: &str) -> Result<UncontestedClaims, ClaimParseError> {
+    let claims = parse_claims(input)?;
+    let mut uncontested = vec![true; claims.len()];
+    for i in 0..claims.len() {
+        for j in i + 1..claims.len() {
+            if claims[i].rect.intersects(&claims[j].rect) {
+                uncontested[i] = false;
+                uncontested[j] = false;
+            }
+        }
+    }
+    Ok(UncontestedClaims(
+        claims
+            .iter()
+            .zip(uncontested)
+            .filter(|(_, uncontested)| *uncontested)
+            .map(|(claim, _)| claim.id)
+            .collect(),
+    ))
+}
+
+#[test]
+fn test_day3_part2_square_iteration_hint() {
+    assert_eq!(
+        day3_part2_square_iteration(HINT_INPUT).unwrap(),
+        UncontestedClaims(vec![HINT_EXPECTED_PART2_OUTPUT])
+    );
+}
+
+#[test]
+fn test_day3_part2_square_iteration_answer() {
+    assert_eq!(
+        day3_part2_square_iteration(INPUT).unwrap(),
+        UncontestedClaims(vec![EXPECTED_PART2_OUTPUT])
+    );
+}
+
+/// Claims touching more cells of a `ClaimIndex` than this are compared against every other claim
+/// instead of being bucketed.
+const MAX_CELLS_PER_CLAIM: usize = 64;
+
+/// A spatial index over claims, bucketing them by the square cells of a uniform grid they touch.
+///
+/// Cells are as large as the median claim's longer side, so a typical claim touches only a few
+/// buckets and overlap queries only compare claims sharing one. The few claims that would touch
+/// more than `MAX_CELLS_PER_CLAIM` cells are kept aside and compared against everything.
+pub struct ClaimIndex<'c> {
+    claims: &'c [Claim],
+    cell_size: usize,
+    buckets: HashMap<(usize, usize), Vec<usize>>,
+    /// Indices of claims too large to bucket
+    oversized: Vec<usize>,
+}
+
+impl<'c> ClaimIndex<'c> {
+    pub fn new(claims: &'c [Claim]) -> Self {
+        let mut sizes = claims
+            .iter()
+            .map(|c| max(c.rect.width(), c.rect.height()))
+            .collect::<Vec<_>>();
+        sizes.sort_unstable();
+        let cell_size = max(sizes.get(sizes.len() / 2).cloned().unwrap_or(1), 1);
+
+        let mut index = Self {
+            claims,
+            cell_size,
+            buckets: HashMap::new(),
+            oversized: Vec::new(),
+        };
+        for (claim_idx, claim) in claims.iter().enumerate() {
+            if index.is_oversized(&claim.rect) {
+                index.oversized.push(claim_idx);
+                continue;
+            }
+            for cell in index.cells_of(&claim.rect) {
+                index.buckets.entry(cell).or_default().push(claim_idx);
+            }
+        }
+        index
+    }
+
+    fn cell_of(&self, (x, y): (usize, usize)) -> (usize, usize) {
+        (x / self.cell_size, y / self.cell_size)
+    }
+
+    fn cell_ranges_of(&self, rect: &Rect) -> (Range<usize>, Range<usize>) {
+        if rect.is_empty() {
+            (1..1, 1..1)
+        } else {
+            let (left, top) = self.cell_of((rect.left(), rect.top()));
+            let (right, bottom) = self.cell_of((rect.right() - 1, rect.bottom() - 1));
+            (left..right + 1, top..bottom + 1)
+        }
+    }
+
+    fn is_oversized(&self, rect: &Rect) -> bool {
+        let (cells_x, cells_y) = self.cell_ranges_of(rect);
+        cells_x.len().saturating_mul(cells_y.len()) > MAX_CELLS_PER_CLAIM
+    }
+
+    fn cells_of(&self, rect: &Rect) -> impl Iterator<Item = (usize, usize)> {
+        let (cells_x, cells_y) = self.cell_ranges_of(rect);
+        cells_y.flat_map(move |y| cells_x.clone().map(move |x| (x, y)))
+    }
+
+    /// Whether `cell` is the one cell in which an overlap between `r1` and `r2` gets reported, so
+    /// overlaps spanning several cells are only reported once.
+    fn reports_overlap_in(&self, cell: (usize, usize), r1: &Rect, r2: &Rect) -> bool {
+        r1.intersection(r2)
+            .map_or(false, |i| self.cell_of((i.left(), i.top())) == cell)
+    }
+
+    /// Indexed claims overlapping `claim`, other than `claim` itself if it's one of them. Distinct
+    /// claims sharing an ID with `claim` are still reported.
+    pub fn overlapping(&self, claim: &Claim) -> Vec<&'c Claim> {
+        let overlaps = |other: &Claim| !ptr::eq(other, claim) && other.intersects(claim);
+        if self.is_oversized(&claim.rect) {
+            return self.claims.iter().filter(|other| overlaps(other)).collect();
+        }
+
+        let mut overlapping = Vec::new();
+        for cell in self.cells_of(&claim.rect) {
+            for &other_idx in self.buckets.get(&cell).into_iter().flatten() {
+                let other = &self.claims[other_idx];
+                if !ptr::eq(other, claim) && self.reports_overlap_in(cell, &claim.rect, &other.rect)
+                {
+                    overlapping.push(other);
+                }
+            }
+        }
+        overlapping.extend(
+            self.oversized
+                .iter()
+                .map(|&other_idx| &self.claims[other_idx])
+                .filter(|other| overlaps(other)),
+        );
+        overlapping
+    }
+
+    /// Every pair of overlapping indexed claims, in no particular order.
+    pub fn overlapping_pairs(&self) -> Vec<(&'c Claim, &'c Claim)> {
+        let mut pairs = Vec::new();
+        for (&cell, bucket) in self.buckets.iter() {
+            for (bucket_idx, &claim_idx) in bucket.iter().enumerate() {
+                let claim = &self.claims[claim_idx];
+                for &other_idx in bucket[bucket_idx + 1..].iter() {
+                    let other = &self.claims[other_idx];
+                    if self.reports_overlap_in(cell, &claim.rect, &other.rect) {
+                        pairs.push((claim, other));
+                    }
+                }
+            }
+        }
+        self.for_each_oversized_pair(|claim_idx, other_idx| {
+            pairs.push((&self.claims[claim_idx], &self.claims[other_idx]))
+        });
+        pairs
+    }
+
+    /// Indexed claims overlapping no other indexed claim, in index order.
+    pub fn uncontested(&self) -> Vec<&'c Claim> {
+        let mut contested = vec![false; self.claims.len()];
+        let mut mark_contested = |claim_idx: usize, other_idx: usize| {
+            contested[claim_idx] = true;
+            contested[other_idx] = true;
+        };
+        for bucket in self.buckets.values() {
+            for (bucket_idx, &claim_idx) in bucket.iter().enumerate() {
+                for &other_idx in bucket[bucket_idx + 1..].iter() {
+                    if self.claims[claim_idx].intersects(&self.claims[other_idx]) {
+                        mark_contested(claim_idx, other_idx);
+                    }
+                }
+            }
+        }
+        self.for_each_oversized_pair(mark_contested);
+        self.claims
+            .iter()
+            .zip(contested)
+            .filter_map(|(c, contested)| if contested { None } else { Some(c) })
+            .collect()
+    }
+
+    /// Calls `f` with the indices of every overlapping pair of claims that includes an oversized
+    /// one, exactly once per pair.
+    fn for_each_oversized_pair(&self, mut f: impl FnMut(usize, usize)) {
+        for &claim_idx in self.oversized.iter() {
+            let claim = &self.claims[claim_idx];
+            for (other_idx, other) in self.claims.iter().enumerate() {
+                // Pairs of oversized claims get visited from both sides.
+                let visited_already = other_idx <= claim_idx && self.is_oversized(&other.rect);
+                if !visited_already && claim.intersects(other) {
+                    f(claim_idx, other_idx);
+                }
+            }
+        }
+    }
+}
+
+/// How a single claim overlaps with the others.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ClaimOverlaps {
+    pub id: usize,
+    /// IDs of the other claims overlapping this one, in increasing order
+    pub overlapping_ids: Vec<usize>,
+    /// Area of this claim also covered by at least one other claim
+    pub overlapped_area: u128,
+}
+
+/// Overlap statistics for a whole set of claims.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FabricReport {
+    claims: Vec<ClaimOverlaps>,
+    max_overlap: Option<(usize, (usize, usize))>,
+}
+
+impl FabricReport {
+    pub fn new(claims: &[Claim]) -> Self {
+        let index = ClaimIndex::new(claims);
+        let claim_overlaps = claims
+            .iter()
+            .map(|claim| {
+                let overlapping = index.overlapping(claim);
+                let intersections = overlapping
+                    .iter()
+                    .filter_map(|other| claim.rect.intersection(&other.rect))
+                    .collect::<Vec<_>>();
+                let mut overlapping_ids = overlapping.iter().map(|c| c.id).collect::<Vec<_>>();
+                overlapping_ids.sort();
+                ClaimOverlaps {
+                    id: claim.id,
+                    overlapping_ids,
+                    overlapped_area: union_area(&intersections),
+                }
+            })
+            .collect();
+
+        Self {
+            claims: claim_overlaps,
+            max_overlap: max_overlap_depth(claims.iter().map(|c| &c.rect)),
+        }
+    }
+
+    /// Overlaps of every claim, in the order the claims were given.
+    pub fn claims(&self) -> &[ClaimOverlaps] {
+        &self.claims
+    }
+
+    /// Indices into `claims` of every claim that doesn't overlap any other claim, in increasing
+    /// order. Claims are told apart by index since several may share an ID.
+    pub fn uncontested(&self) -> Vec<usize> {
+        self.claims
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.overlapping_ids.is_empty())
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+
+    /// The greatest number of claims covering a single square inch, along with the leftmost (and
+    /// then topmost) square inch covered by that many. Returns `None` if there are no claims.
+    pub fn max_overlap(&self) -> Option<(usize, (usize, usize))> {
+        self.max_overlap
+    }
+}
+
+#[aoc(day3, part2, spatial_index)]
+pub fn day3_part2_spatial_index(input: &str) -> Result<UncontestedClaims, ClaimParseError> {
+    Ok(uncontested_claims(&parse_claims(input)?))
+}
+
+fn uncontested_claims(claims: &[Claim]) -> UncontestedClaims {
+    UncontestedClaims(
+        FabricReport::new(claims)
+            .uncontested()
+            .into_iter()
+            .map(|idx| claims[idx].id)
+            .collect(),
+    )
+}
+
+#[test]
+fn test_day3_part2_spatial_index_hint() {
+    assert_eq!(
+        day3_part2_spatial_index(HINT_INPUT).unwrap(),
+        UncontestedClaims(vec![HINT_EXPECTED_PART2_OUTPUT])
+    );
+}
+
+#[test]
+fn test_day3_part2_spatial_index_answer() {
+    assert_eq!(
+        day3_part2_spatial_index(INPUT).unwrap(),
+        UncontestedClaims(vec![EXPECTED_PART2_OUTPUT])
+    );
+}
+
+#[test]
+fn test_fabric_report_hint() {
+    let claims = parse_claims(HINT_INPUT).unwrap();
+    let report = FabricReport::new(&claims);
+    assert_eq!(
+        report.claims(),
+        &[
+            ClaimOverlaps {
+                id: 1,
+                overlapping_ids: vec![2],
+                overlapped_area: 4,
+            },
+            ClaimOverlaps {
+                id: 2,
+                overlapping_ids: vec![1],
+                overlapped_area: 4,
+            },
+            ClaimOverlaps {
+                id: 3,
+                overlapping_ids: vec![],
+                overlapped_area: 0,
+            },
+        ][..]
+    );
+    assert_eq!(report.uncontested(), [2]);
+    assert_eq!(report.max_overlap(), Some((2, (3, 3))));
+}
+
+#[test]
+fn test_fabric_report_uncontested_counts() {
+    let none_uncontested = "#1 @ 0,0: 2x2\n#2 @ 1,1: 2x2\n#3 @ 1,0: 1x3\n";
+    let claims = parse_claims(none_uncontested).unwrap();
+    let report = FabricReport::new(&claims);
+    assert!(report.uncontested().is_empty());
+    assert_eq!(report.claims()[2].overlapping_ids, [1, 2]);
+    assert_eq!(report.claims()[2].overlapped_area, 3);
+    assert_eq!(report.max_overlap(), Some((3, (1, 1))));
+    assert_eq!(
+        day3_part2_spatial_index(none_uncontested).unwrap(),
+        UncontestedClaims(vec![])
+    );
+
+    let all_uncontested = "#7 @ 0,0: 1x1\n#8 @ 5,5: 1x1\n";
+    assert_eq!(
+        day3_part2_spatial_index(all_uncontested)
+            .unwrap()
+            .to_string(),
+        "7,8"
+    );
+    assert_eq!(
+        day3_part2_square_iteration(all_uncontested)
+            .unwrap()
+            .to_string(),
+        "7,8"
+    );
+    assert_eq!(
+        day3_part2_grid_again(all_uncontested).unwrap().to_string(),
+        "7,8"
+    );
+
+    assert_eq!(FabricReport::new(&[]).max_overlap(), None);
+
+    // Only one of the claims with ID 1 is contested.
+    let shared_ids = "#1 @ 0,0: 2x2\n#2 @ 1,1: 1x1\n#1 @ 5,5: 1x1\n";
+    let report = FabricReport::new(&parse_claims(shared_ids).unwrap());
+    assert_eq!(report.uncontested(), [2]);
+    assert_eq!(
+        day3_part2_spatial_index(shared_ids).unwrap().to_string(),
+        "1"
+    );
+    assert_eq!(
+        day3_part2_square_iteration(shared_ids).unwrap().to_string(),
+        "1"
+    );
+    let shared_ids = "#1 @ 0,0: 2x2\n#2 @ 1,1: 1x1\n#1 @ 1,0: 1x1\n";
+    assert!(FabricReport::new(&parse_claims(shared_ids).unwrap())
+        .uncontested()
+        .is_empty());
+    assert_eq!(
+        day3_part2_spatial_index(shared_ids).unwrap().to_string(),
+        ""
+    );
+
+    // Overlaps too big to count in a `usize`
+    let huge = "#1 @ 0,0: 5000000000x5000000000\n#2 @ 0,0: 5000000000x5000000000\n";
+    let report = FabricReport::new(&parse_claims(huge).unwrap());
+    assert_eq!(
+        report.claims()[0].overlapped_area,
+        25_000_000_000_000_000_000
+    );
+    assert_eq!(
+        report.claims()[1].overlapped_area,
+        25_000_000_000_000_000_000
+    );
+}
+
+#[test]
+fn test_fabric_report_answer() {
+    let claims = parse_claims(INPUT).unwrap();
+    let report = FabricReport::new(&claims);
+    let uncontested = report.uncontested();
+    assert_eq!(uncontested.len(), 1);
+    assert_eq!(claims[uncontested[0]].id(), EXPECTED_PART2_OUTPUT);
+    assert_eq!(report.max_overlap().map(|(depth, _)| depth), Some(7));
+}
+
+#[cfg(test)]
+fn assert_claim_index_matches_brute_force(claims: &[Claim]) {
+    let index = ClaimIndex::new(claims);
+
+    let mut expected_pairs = Vec::new();
+    for (i, claim) in claims.iter().enumerate() {
+        for (j, other) in claims.iter().enumerate().skip(i + 1) {
+            if claim.intersects(other) {
+                expected_pairs.push((i, j));
+            }
+        }
+    }
+    let idx_of = |c: &Claim| claims.iter().position(|other| ptr::eq(other, c)).unwrap();
+    let mut pairs = index
+        .overlapping_pairs()
+        .into_iter()
+        .map(|(c1, c2)| (min(idx_of(c1), idx_of(c2)), max(idx_of(c1), idx_of(c2))))
+        .collect::<Vec<_>>();
+    pairs.sort();
+    assert_eq!(pairs, expected_pairs);
+
+    for (i, claim) in claims.iter().enumerate() {
+        let mut overlapping = index
+            .overlapping(claim)
+            .into_iter()
+            .map(idx_of)
+            .collect::<Vec<_>>();
+        overlapping.sort();
+        let expected = (0..claims.len())
+            .filter(|&j| j != i && claims[j].intersects(claim))
+            .collect::<Vec<_>>();
+        assert_eq!(overlapping, expected);
+    }
+
+    let uncontested = index
+        .uncontested()
+        .into_iter()
+        .map(idx_of)
+        .collect::<Vec<_>>();
+    let expected = (0..claims.len())
+        .filter(|&i| expected_pairs.iter().all(|&(j, k)| i != j && i != k))
+        .collect::<Vec<_>>();
+    assert_eq!(uncontested, expected);
+}
+
+#[test]
+fn test_claim_index_matches_brute_force() {
+    assert_claim_index_matches_brute_force(&parse_claims(INPUT).unwrap());
+}
+
+#[test]
+fn test_claim_index_oversized_claims() {
+    // Many small claims make for small cells, which the huge claims would otherwise cover
+    // trillions of.
+    let mut claims = (0..500)
+        .map(|i| Claim {
+            id: i % 50,
+            rect: Rect::from_origin_and_size(i * 7 % 1000, i * 13 % 1000, 3, 2).unwrap(),
+        })
+        .collect::<Vec<_>>();
+    for &(id, left, top, size) in &[
+        (1000, 0, 0, 1_000_000_000),
+        (1001, 999_000_000, 999_000_000, 1_000_000_000),
+        (1002, 500, 2_000_000_000, 1_000_000),
+        (1002, 500, 2_000_000_000, 1_000_000),
+    ] {
+        claims.push(Claim {
+            id,
+            rect: Rect::from_origin_and_size(left, top, size, size).unwrap(),
+        });
+    }
+    claims.rotate_right(2);
+    assert_claim_index_matches_brute_force(&claims);
+}
+
+#[test]
+fn test_claim_index_many_claims() {
+    use std::collections::HashSet;
+
+    let mut seed: u64 = 2018;
+    let mut random = |bound: u64| {
+        seed = seed
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        ((seed >> 33) % bound) as usize
+    };
+    let claims = (0..1_000_000)
+        .map(|id| Claim {
+            id,
+            rect: Rect::from_origin_and_size(
+                random(1_000_000),
+                random(1_000_000),
+                1 + random(100),
+                1 + random(100),
+            )
+            .unwrap(),
+        })
+        .collect::<Vec<_>>();
+    let index = ClaimIndex::new(&claims);
+    let uncontested = index.uncontested();
+    let num_contested = index
+        .overlapping_pairs()
+        .into_iter()
+        .flat_map(|(c1, c2)| vec![c1.id, c2.id])
+        .collect::<HashSet<_>>()
+        .len();
+    assert_eq!(uncontested.len() + num_contested, claims.len());
+    for claim in uncontested.iter().take(100) {
+        assert!(index.overlapping(claim).is_empty());
+    }
+}
+
+/// Finds the claims not overlapping any other claim, using `grid` to count claims with `C` like
+/// `day3_part1_with_grid` does.
+pub fn day3_part2_with_grid<C, G>(
+    claims: &[Claim],
+    grid: &mut G,
+) -> Result<UncontestedClaims, ClaimCountOverflow>
+where
+    C: ClaimCount,
+    G: CellGrid<C>,
+{
+    count_claims_in(grid, claims)?;
+
+    let uncontested = claims
+        .iter()
+        .filter(|Claim { rect, .. }| {
+            for y in rect.top()..rect.bottom() {
+                for x in rect.left()..rect.right() {
+                    let count = grid[(x, y)];
+                    assert!(count != C::default());
+                    if count > C::ONE {
+                        return false;
+                    }
+                }
+            }
+            true
+        })
+        .map(|c| c.id)
+        .collect();
+    Ok(UncontestedClaims(uncontested))
+}
+
+#[aoc(day3, part2, grid_again)]
+pub fn day3_part2_grid_again(input: &str) -> Result<UncontestedClaims, ClaimParseError> {
+    let claims = parse_claims(input)?;
+    let mut grid = GrowOnlyGrid::<Saturating<u8>>::new_with(1000, 1000, Default::default);
+    // `Saturating` counts never overflow.
+    Ok(day3_part2_with_grid(&claims, &mut grid).unwrap())
+}
+
+#[aoc(day3, part2, sparse_grid)]
+pub fn day3_part2_sparse_grid(input: &str) -> Result<UncontestedClaims, ClaimParseError> {
+    let claims = parse_claims(input)?;
+    // `Saturating` counts never overflow.
+    Ok(day3_part2_with_grid(&claims, &mut SparseGrid::new(Saturating(0u8))).unwrap())
+}
+
+#[test]
+fn test_day3_part2_grid_again_hint() {
+    assert_eq!(
+        day3_part2_grid_again(HINT_INPUT).unwrap(),
+        UncontestedClaims(vec![HINT_EXPECTED_PART2_OUTPUT])
+    );
+}
+
+#[test]
+fn test_day3_part2_grid_again_answer() {
+    assert_eq!(
+        day3_part2_grid_again(INPUT).unwrap(),
+        UncontestedClaims(vec![EXPECTED_PART2_OUTPUT])
+    );
+}
+
+#[test]
+fn test_day3_part2_sparse_grid_hint() {
+    assert_eq!(
+        day3_part2_sparse_grid(HINT_INPUT).unwrap(),
+        UncontestedClaims(vec![HINT_EXPECTED_PART2_OUTPUT])
+    );
+}
+
+#[test]
+fn test_day3_part2_sparse_grid_answer() {
+    assert_eq!(
+        day3_part2_sparse_grid(INPUT).unwrap(),
+        UncontestedClaims(vec![EXPECTED_PART2_OUTPUT])
+    );
+}
+
+#[test]
+fn test_day3_sparse_grid_huge_coordinates() {
+    const INPUT: &str = "#1 @ 999999990,999999990: 5x5
+#2 @ 999999993,999999993: 5x5
+#3 @ 3,999999999: 2x1
+#4 @ 500000000,0: 10x10
+#5 @ 500000005,5: 10x10
+#6 @ 0,0: 1x1
+";
+    assert_eq!(day3_part1_sparse_grid(INPUT).unwrap(), 2 * 2 + 5 * 5);
+    assert_eq!(
+        day3_part2_sparse_grid(INPUT).unwrap(),
+        UncontestedClaims(vec![3, 6])
+    );
+}
+
+/// Renders the fabric under a set of claims from a grid of how many claims cover each square inch
+/// (as filled in by `count_claims_in`), for eyeballing what went wrong with an answer.
+///
+/// Only the bounding box of the claims is rendered. Cells of highlighted claims stand out from the
+/// rest, in a way that shows whether they're contested.
+pub struct FabricRender<'a, C = u8> {
+    counts: &'a GrowOnlyGrid<C>,
+    bounds: Option<Rect>,
+    highlighted: Vec<Rect>,
+}
+
+impl<'a, C: ClaimCount> FabricRender<'a, C> {
+    pub fn new(counts: &'a GrowOnlyGrid<C>, claims: &[Claim]) -> Self {
+        Self {
+            counts,
+            bounds: claims.iter().map(|c| c.rect).fold(None, |bounds, rect| {
+                Some(bounds.map_or(rect, |b: Rect| b.union_bounds(&rect)))
+            }),
+            highlighted: Vec::new(),
+        }
+    }
+
+    /// Highlights the claims in `claims` whose IDs are in `ids`.
+    pub fn highlight(mut self, claims: &[Claim], ids: &[usize]) -> Self {
+        self.highlighted.extend(
+            claims
+                .iter()
+                .filter(|c| ids.contains(&c.id))
+                .map(|c| c.rect),
+        );
+        self
+    }
+
+    /// The rendered cells with how many claims cover them and whether they're highlighted, row by
+    /// row.
+    fn rows(&self) -> impl Iterator<Item = impl Iterator<Item = (u64, bool)> + '_> + '_ {
+        let (width, height) = self.dimensions();
+        let (left, top) = self.bounds.map_or((0, 0), |b| (b.left(), b.top()));
+        (top..top + height).map(move |y| {
+            (left..left + width).map(move |x| {
+                let count = self
+                    .counts
+                    .get((x as isize, y as isize))
+                    .map_or(0, |c| c.to_u64());
+                let highlighted = self.highlighted.iter().any(|r| r.contains_point((x, y)));
+                (count, highlighted)
+            })
+        })
+    }
+
+    /// The width and height of the rendered fabric.
+    pub fn dimensions(&self) -> (usize, usize) {
+        self.bounds.map_or((0, 0), |b| (b.width(), b.height()))
+    }
+
+    /// Writes one line of text per row, with `.` for cells not covered by any claim, `1` to `9`
+    /// for how many claims cover the others, and `+` for more than that. Highlighted cells are
+    /// `#` if only one claim covers them, or `X` otherwise.
+    pub fn write_ascii<W: Write>(&self, mut out: W) -> io::Result<()> {
+        for row in self.rows() {
+            let line = row
+                .map(|(count, highlighted)| match (count, highlighted) {
+                    (1, true) => '#',
+                    (_, true) => 'X',
+                    (0, false) => '.',
+                    (1..=9, false) => (b'0' + count as u8) as char,
+                    (_, false) => '+',
+                })
+                .collect::<String>();
+            writeln!(out, "{}", line)?;
+        }
+        Ok(())
+    }
+
+    /// Writes a binary PGM image where brighter pixels are covered by more claims. Highlighting
+    /// doesn't show up in it.
+    pub fn write_pgm<W: Write>(&self, mut out: W) -> io::Result<()> {
+        let (width, height) = self.dimensions();
+        let max_count = self.max_count();
+        write!(out, "P5\n{} {}\n255\n", width, height)?;
+        for row in self.rows() {
+            let pixels = row
+                .map(|(count, _)| brightness(count, max_count))
+                .collect::<Vec<_>>();
+            out.write_all(&pixels)?;
+        }
+        Ok(())
+    }
+
+    /// Writes a binary PPM image where brighter grey pixels are covered by more claims.
+    /// Highlighted cells are green if only one claim covers them, or red otherwise.
+    pub fn write_ppm<W: Write>(&self, mut out: W) -> io::Result<()> {
+        let (width, height) = self.dimensions();
+        let max_count = self.max_count();
+        write!(out, "P6\n{} {}\n255\n", width, height)?;
+        for row in self.rows() {
+            let pixels = row
+                .flat_map(|(count, highlighted)| match (count, highlighted) {
+                    (1, true) => [0, 255, 0],
+                    (_, true) => [255, 0, 0],
+                    (_, false) => [brightness(count, max_count); 3],
+                })
+                .collect::<Vec<_>>();
+            out.write_all(&pixels)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the render to a file, as ASCII art if its extension is `txt`, a PGM image if it's
+    /// `pgm`, and a PPM image otherwise.
+    pub fn write_to_path<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        let out = BufWriter::new(File::create(path)?);
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("txt") => self.write_ascii(out),
+            Some("pgm") => self.write_pgm(out),
+            _ => self.write_ppm(out),
+        }
+    }
+
+    fn max_count(&self) -> u64 {
+        self.rows()
+            .flat_map(|row| row.map(|(count, _)| count))
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+fn brightness(count: u64, max_count: u64) -> u8 {
+    if max_count == 0 {
+        0
+    } else {
+        (u128::from(count) * 255 / u128::from(max_count)) as u8
+    }
+}
+
+#[cfg(test)]
+fn hint_fabric() -> (Vec<Claim>, GrowOnlyGrid<u8>) {
+    let claims = parse_claims(HINT_INPUT).unwrap();
+    let mut grid = GrowOnlyGrid::new_with(0, 0, Default::default);
+    count_claims_in(&mut grid, &claims).unwrap();
+    (claims, grid)
+}
+
+#[test]
+fn test_render_ascii() {
+    let (claims, grid) = hint_fabric();
+    let mut out = Vec::new();
+    FabricRender::new(&grid, &claims)
+        .write_ascii(&mut out)
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "..1111\n..1111\n112211\n112211\n111111\n111111\n"
+    );
+
+    let mut out = Vec::new();
+    FabricRender::new(&grid, &claims)
+        .highlight(&claims, &[1, 3])
+        .write_ascii(&mut out)
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "..1111\n..1111\n##XX11\n##XX11\n######\n######\n"
+    );
+}
+
+#[test]
+fn test_render_images() {
+    let (claims, grid) = hint_fabric();
+    let render = FabricRender::new(&grid, &claims).highlight(&claims, &[3]);
+
+    let mut pgm = Vec::new();
+    render.write_pgm(&mut pgm).unwrap();
+    let header = b"P5\n6 6\n255\n";
+    assert_eq!(&pgm[..header.len()], header);
+    assert_eq!(
+        &pgm[header.len()..header.len() + 12],
+        [0, 0, 127, 127, 127, 127, 0, 0, 127, 127, 127, 127]
+    );
+    assert_eq!(pgm[header.len() + 6 * 2 + 2], 255);
+    assert_eq!(pgm.len(), header.len() + 6 * 6);
+
+    let mut ppm = Vec::new();
+    render.write_ppm(&mut ppm).unwrap();
+    let header = b"P6\n6 6\n255\n";
+    assert_eq!(&ppm[..header.len()], header);
+    assert_eq!(ppm.len(), header.len() + 6 * 6 * 3);
+    let pixel = |x: usize, y: usize| {
+        let start = header.len() + (y * 6 + x) * 3;
+        &ppm[start..start + 3]
+    };
+    assert_eq!(pixel(0, 0), [0, 0, 0]);
+    assert_eq!(pixel(2, 2), [255, 255, 255]);
+    assert_eq!(pixel(5, 5), [0, 255, 0]);
+
+    let path = std::env::temp_dir().join(format!("day3-render-{}.txt", std::process::id()));
+    render.write_to_path(&path).unwrap();
+    let written = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(written.ends_with("1111##\n1111##\n"));
+}
+
+#[test]
+fn test_day3_grid_counts_past_u8() {
+    let claims = (1..=300)
+        .map(|id| format!("#{} @ 0,0: 2x2", id))
+        .chain(Some("#301 @ 10,10: 1x1".to_owned()))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let claims = parse_claims(&claims).unwrap();
+    let overflow = ClaimCountOverflow { x: 0, y: 0 };
+
+    let mut narrow = GrowOnlyGrid::<u8>::new_with(0, 0, Default::default);
+    let mut saturating = GrowOnlyGrid::<Saturating<u8>>::new_with(0, 0, Default::default);
+    let mut wide = GrowOnlyGrid::<u16>::new_with(0, 0, Default::default);
+    assert_eq!(day3_part1_with_grid(&claims, &mut narrow), Err(overflow));
+    assert_eq!(day3_part1_with_grid(&claims, &mut saturating), Ok(4));
+    assert_eq!(day3_part1_with_grid(&claims, &mut wide), Ok(4));
+    assert_eq!(
+        day3_part2_with_grid::<u8, _>(&claims, &mut SparseGrid::new(0)),
+        Err(overflow)
+    );
+    assert_eq!(
+        day3_part2_with_grid(&claims, &mut SparseGrid::new(Saturating(0u8))),
+        Ok(UncontestedClaims(vec![301]))
+    );
+    assert_eq!(
+        day3_part2_with_grid(&claims, &mut SparseGrid::new(0u16)),
+        Ok(UncontestedClaims(vec![301]))
+    );
+
+    let mut out = Vec::new();
+    FabricRender::new(&wide, &claims[..3])
+        .write_ascii(&mut out)
+        .unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), "++\n++\n");
 }
@@ -1,16 +1,16 @@
 use {
     aoc_runner_derive::aoc,
-    arrayvec::ArrayVec,
-    chrono::{NaiveDateTime, Timelike},
-    derive_more::{Display, Sub},
+    chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime, Timelike},
+    derive_more::Display,
     lazy_static::lazy_static,
     re_parse::Regex,
     std::{
         cmp::Ordering::*,
-        collections::BTreeMap,
+        collections::{BTreeMap, BTreeSet},
+        error::Error,
         fmt::{Display, Formatter, Result as FmtResult},
+        str::FromStr,
     },
-    try_from::TryFrom,
 };
 
 #[cfg(test)]
@@ -36,15 +36,15 @@ const HINT_INPUT: &'static str = r#"[1518-11-01 00:00] Guard #10 begins shift
 #[cfg(test)]
 const INPUT: &'static str = include_str!("../input/2018/day4.txt");
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
 pub struct Answer {
     guard_id: GuardId,
-    minute: Minute,
+    time: NaiveTime,
 }
 
 impl Display for Answer {
     fn fmt(&self, f: &mut Formatter) -> FmtResult {
-        write!(f, "{}", self.minute.0 as u32 * self.guard_id.0)
+        write!(f, "{}", self.time.minute() * self.guard_id.0)
     }
 }
 
@@ -52,237 +52,689 @@ impl Display for Answer {
 fn test_day4_part1_hint() {
     assert_eq!(
         day4_part1(HINT_INPUT),
-        Answer {
+        Ok(Answer {
             guard_id: GuardId(10),
-            minute: Minute(24),
-        }
+            time: NaiveTime::from_hms(0, 24, 0),
+        })
     );
 }
 
 #[derive(Clone, Copy, Debug, Display, Eq, Ord, PartialEq, PartialOrd)]
 #[display(fmt = "{}", _0)]
-struct GuardId(u32);
-
-#[derive(Clone, Copy, Debug, Display, Eq, Ord, PartialEq, PartialOrd, Sub)]
-#[display(fmt = "{}", _0)]
-struct Minute(u8);
+pub struct GuardId(pub u32);
 
 const MINUTES_PER_HOUR: u8 = 60;
 
-#[derive(Debug)]
-struct MinuteConversionError(pub u8);
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum GuardEvent {
+    ShiftChange { new_guard_id: GuardId },
+    FallAsleep,
+    WakeUp,
+}
 
-impl TryFrom<u8> for Minute {
-    type Err = MinuteConversionError;
+/// A problem with a guard log, along with the line it was found on.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum GuardLogError {
+    MalformedTimestamp {
+        line_number: usize,
+        line: String,
+    },
+    UnknownEvent {
+        line_number: usize,
+        line: String,
+    },
+    /// A guard fell asleep before any guard began their shift.
+    SleepWithoutGuard {
+        line_number: usize,
+        line: String,
+    },
+    /// A guard woke up without having fallen asleep.
+    WakeWithoutSleep {
+        line_number: usize,
+        line: String,
+    },
+    /// A guard fell asleep and didn't wake up before doing anything else, or before the log ended.
+    SleepWithoutWake {
+        line_number: usize,
+        line: String,
+    },
+    /// Another line was logged at the same time.
+    DuplicateTimestamp {
+        line_number: usize,
+        line: String,
+    },
+}
 
-    fn try_from(m: u8) -> Result<Self, Self::Err> {
-        match m {
-            m if m < MINUTES_PER_HOUR => Ok(Minute(m)),
-            m => Err(MinuteConversionError(m)),
+impl GuardLogError {
+    /// The 1-based line number and text of the offending line.
+    pub fn line(&self) -> (usize, &str) {
+        use self::GuardLogError::*;
+
+        match self {
+            MalformedTimestamp { line_number, line }
+            | UnknownEvent { line_number, line }
+            | SleepWithoutGuard { line_number, line }
+            | WakeWithoutSleep { line_number, line }
+            | SleepWithoutWake { line_number, line }
+            | DuplicateTimestamp { line_number, line } => (*line_number, line),
         }
     }
 }
 
-#[derive(Debug)]
-enum GuardEvent {
-    ShiftChange { new_guard_id: GuardId },
-    FallAsleep,
-    WakeUp,
+impl Display for GuardLogError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        use self::GuardLogError::*;
+
+        let (line_number, line) = self.line();
+        write!(f, "line {} ({:?}): ", line_number, line)?;
+        match self {
+            MalformedTimestamp { .. } => write!(f, "expected a `[YYYY-MM-DD HH:MM]` timestamp"),
+            UnknownEvent { .. } => write!(f, "unrecognized event"),
+            SleepWithoutGuard { .. } => write!(f, "nobody is on duty to fall asleep"),
+            WakeWithoutSleep { .. } => write!(f, "guard wakes up without being asleep"),
+            SleepWithoutWake { .. } => write!(f, "guard falls asleep and never wakes up"),
+            DuplicateTimestamp { .. } => write!(f, "another event happened at the same time"),
+        }
+    }
 }
 
-type MinuteCounts = [u8; MINUTES_PER_HOUR as usize - 1];
+impl Error for GuardLogError {}
 
-fn process_input<F: FnMut(GuardId, (Minute, Minute))>(
-    input: &str,
-    mut f: F,
-) -> BTreeMap<GuardId, MinuteCounts> {
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Day4Error {
+    Log(GuardLogError),
+    /// No guard was ever asleep during the minutes being counted.
+    NobodySlept,
+}
+
+impl Display for Day4Error {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Day4Error::Log(e) => write!(f, "{}", e),
+            Day4Error::NobodySlept => write!(f, "no guard was ever asleep"),
+        }
+    }
+}
+
+impl Error for Day4Error {}
+
+impl From<GuardLogError> for Day4Error {
+    fn from(e: GuardLogError) -> Self {
+        Day4Error::Log(e)
+    }
+}
+
+/// An event from a guard log.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct LogEntry {
+    pub time: NaiveDateTime,
+    pub event: GuardEvent,
+}
+
+fn parse_log_line(line_number: usize, line: &str) -> Result<LogEntry, GuardLogError> {
+    use self::GuardEvent::*;
+
+    let malformed_timestamp = || GuardLogError::MalformedTimestamp {
+        line_number,
+        line: line.to_owned(),
+    };
+    let unknown_event = || GuardLogError::UnknownEvent {
+        line_number,
+        line: line.to_owned(),
+    };
+
+    let (time, event) = line.split_at(line.find(']').ok_or_else(malformed_timestamp)? + 1);
+    let time =
+        NaiveDateTime::parse_from_str(time, "[%Y-%m-%d %R]").map_err(|_| malformed_timestamp())?;
+
+    lazy_static! {
+        static ref REGEX_SHIFT_CHANGE: Regex =
+            Regex::new(r#"^Guard #(?P<new_guard_id>\d+) begins shift$"#).unwrap();
+    }
+    let event = match event.trim_start() {
+        "wakes up" => WakeUp,
+        "falls asleep" => FallAsleep,
+        other => ShiftChange {
+            new_guard_id: REGEX_SHIFT_CHANGE
+                .captures(other)
+                .and_then(|c| c["new_guard_id"].parse().ok())
+                .map(GuardId)
+                .ok_or_else(unknown_event)?,
+        },
+    };
+    Ok(LogEntry { time, event })
+}
+
+/// Parses every line of a guard log and puts them in chronological order, making sure every nap
+/// is taken by a guard on duty and ends before anything else happens.
+pub fn parse_guard_log(input: &str) -> Result<Vec<LogEntry>, GuardLogError> {
     use self::GuardEvent::*;
 
     let mut log = BTreeMap::new();
-    for line in input.split('\n') {
-        if line == "" {
+    for (line_idx, line) in input.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
             continue;
         }
-
-        let (time, event) = {
-            let (time, mut guard_event_str) = line.split_at(line.find(']').unwrap() + 1);
-            (
-                NaiveDateTime::parse_from_str(time, "[%Y-%m-%d %R]").unwrap(),
-                {
-                    lazy_static! {
-                        static ref REGEX_SHIFT_CHANGE: Regex =
-                            Regex::new(r#"Guard #(?P<new_guard_id>\d{1,4}) begins shift"#).unwrap();
-                    }
-                    assert_eq!(guard_event_str.chars().next().unwrap(), ' ');
-                    guard_event_str = &guard_event_str[1..];
-                    match guard_event_str {
-                        "wakes up" => WakeUp,
-                        "falls asleep" => FallAsleep,
-                        other => ShiftChange {
-                            new_guard_id: REGEX_SHIFT_CHANGE.captures(other).unwrap()
-                                ["new_guard_id"]
-                                .parse()
-                                .map(GuardId)
-                                .unwrap(),
-                        },
-                    }
-                },
-            )
-        };
-        log.insert(time, event);
+        let line_number = line_idx + 1;
+        let entry = parse_log_line(line_number, line)?;
+        if log.insert(entry.time, (entry, line_number, line)).is_some() {
+            return Err(GuardLogError::DuplicateTimestamp {
+                line_number,
+                line: line.to_owned(),
+            });
+        }
     }
 
-    let mut guard_midnight_sleep_minutes = BTreeMap::new();
-    let mut log_events = log.into_iter();
-    let mut next_event = log_events.next();
-    'shift: loop {
-        let current_guard_id = match next_event {
-            Some((_, ShiftChange { new_guard_id })) => new_guard_id,
-            Some(other) => panic!("expected shift change as next event, got {:?}", other),
-            None => break,
-        };
-        next_event = log_events.next();
-        loop {
-            let start_time = match next_event {
-                Some((_, ShiftChange { .. })) => break,
-                Some((time, FallAsleep)) => time,
-                None => break 'shift,
-                other => panic!(
-                    "expected guard falling asleep as next event, got {:?}",
-                    other
-                ),
-            };
-            let end_time = match log_events.next() {
-                Some((time, WakeUp)) => time,
-                other => panic!("expected guard waking up as next event, got {:?}", other),
-            };
-            // Make sure the guard sleeps during midnight hour like instructions say
-            assert_eq!(start_time.hour(), 0);
-            assert_eq!(end_time.hour(), 0);
-
-            let start_minute = Minute::try_from(start_time.minute() as u8).unwrap();
-            let end_minute = Minute::try_from(end_time.minute() as u8).unwrap();
-            assert!(start_minute < end_minute);
-
-            let minute_counts = guard_midnight_sleep_minutes
-                .entry(current_guard_id)
-                .or_insert_with(|| [0u8; MINUTES_PER_HOUR as usize - 1]);
-            for minute_count in &mut minute_counts[start_minute.0 as usize..end_minute.0 as usize] {
-                *minute_count += 1;
+    let mut on_duty = false;
+    let mut asleep_since = None;
+    for &(entry, line_number, line) in log.values() {
+        if let Some((sleep_line_number, sleep_line)) = asleep_since {
+            if entry.event != WakeUp {
+                return Err(GuardLogError::SleepWithoutWake {
+                    line_number: sleep_line_number,
+                    line: String::from(sleep_line),
+                });
+            }
+        }
+        match entry.event {
+            ShiftChange { .. } => on_duty = true,
+            FallAsleep if !on_duty => {
+                return Err(GuardLogError::SleepWithoutGuard {
+                    line_number,
+                    line: line.to_owned(),
+                })
+            }
+            FallAsleep => asleep_since = Some((line_number, line)),
+            WakeUp if asleep_since.is_none() => {
+                return Err(GuardLogError::WakeWithoutSleep {
+                    line_number,
+                    line: line.to_owned(),
+                })
             }
-            f(current_guard_id, (start_minute, end_minute));
+            WakeUp => asleep_since = None,
+        }
+    }
+    if let Some((line_number, line)) = asleep_since {
+        return Err(GuardLogError::SleepWithoutWake {
+            line_number,
+            line: line.to_owned(),
+        });
+    }
+
+    Ok(log.into_iter().map(|(_, (entry, _, _))| entry).collect())
+}
+
+/// A nap taken by a guard, from the minute they fell asleep up to the minute they woke up. Naps
+/// may span any number of hours or days.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SleepInterval {
+    pub guard_id: GuardId,
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+}
+
+impl SleepInterval {
+    pub fn duration(&self) -> Duration {
+        self.end - self.start
+    }
+
+    /// The start of every minute the guard was asleep for.
+    pub fn minutes(&self) -> impl Iterator<Item = NaiveDateTime> {
+        let Self { start, end, .. } = *self;
+        (0..)
+            .map(move |minutes| start + Duration::minutes(minutes))
+            .take_while(move |&minute| minute < end)
+    }
+}
+
+/// Every nap in a guard log, in chronological order. Use `GuardLog` to group them by shift.
+pub fn parse_sleep_intervals(input: &str) -> Result<Vec<SleepInterval>, GuardLogError> {
+    Ok(GuardLog::parse(input)?.naps().cloned().collect())
+}
+
+const MINUTES_PER_DAY: u32 = 24 * MINUTES_PER_HOUR as u32;
+
+/// A stretch of every day to count minutes of sleep in, with a bucket for each of its minutes.
+/// Windows may wrap around midnight.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MinuteWindow {
+    start: NaiveTime,
+    len_minutes: u32,
+}
+
+impl MinuteWindow {
+    /// Panics if `start` isn't at the start of a minute, or if the window is empty or longer than a
+    /// day.
+    pub fn new(start: NaiveTime, len_minutes: u32) -> Self {
+        assert!(
+            start.second() == 0 && start.nanosecond() == 0,
+            "window must start on a minute, not at {}",
+            start
+        );
+        assert!(
+            len_minutes > 0 && len_minutes <= MINUTES_PER_DAY,
+            "window must be between 1 minute and a day long, not {} minutes",
+            len_minutes
+        );
+        Self { start, len_minutes }
+    }
+
+    /// The midnight hour from 00:00 to 00:59, which is when the puzzle's guards sleep.
+    pub fn midnight_hour() -> Self {
+        Self::new(NaiveTime::from_hms(0, 0, 0), MINUTES_PER_HOUR.into())
+    }
+
+    pub fn start(&self) -> NaiveTime {
+        self.start
+    }
 
-            next_event = log_events.next();
+    pub fn len_minutes(&self) -> u32 {
+        self.len_minutes
+    }
+
+    /// The bucket for the minute `time` is in, if that minute is within the window.
+    pub fn bucket_of(&self, time: NaiveDateTime) -> Option<usize> {
+        let since_start = (time.time() - self.start)
+            .num_seconds()
+            .div_euclid(60)
+            .rem_euclid(MINUTES_PER_DAY.into());
+        if since_start < self.len_minutes.into() {
+            Some(since_start as usize)
+        } else {
+            None
         }
     }
 
-    guard_midnight_sleep_minutes
+    /// The time of day the minute for `bucket` starts at.
+    pub fn bucket_start(&self, bucket: usize) -> NaiveTime {
+        self.start + Duration::minutes(bucket as i64)
+    }
 }
 
-fn most_common_minutes(
-    minute_counts: &MinuteCounts,
-) -> (ArrayVec<[Minute; MINUTES_PER_HOUR as usize - 1]>, u8) {
-    let mut minutes = ArrayVec::<[Minute; MINUTES_PER_HOUR as usize - 1]>::new();
-    let mut highest_minute_count_seen = 0;
-    for (minute, count) in minute_counts.iter().enumerate() {
-        let minute = Minute::try_from(minute as u8).unwrap();
-        match count.cmp(&highest_minute_count_seen) {
-            Greater => {
-                highest_minute_count_seen = *count;
-                minutes.clear();
-                minutes.push(minute);
+impl Default for MinuteWindow {
+    fn default() -> Self {
+        Self::midnight_hour()
+    }
+}
+
+/// How many times a guard was asleep during each minute of a `MinuteWindow`, with a bucket for
+/// each minute, like 60 of them for the midnight hour.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MinuteHistogram {
+    window: MinuteWindow,
+    counts: Vec<u32>,
+}
+
+impl MinuteHistogram {
+    pub fn new(window: MinuteWindow) -> Self {
+        Self {
+            window,
+            counts: vec![0; window.len_minutes() as usize],
+        }
+    }
+
+    pub fn window(&self) -> MinuteWindow {
+        self.window
+    }
+
+    /// Counts every minute of `interval` that's within the window.
+    pub fn add(&mut self, interval: &SleepInterval) {
+        for minute in interval.minutes() {
+            if let Some(bucket) = self.window.bucket_of(minute) {
+                self.counts[bucket] += 1;
             }
-            Equal => minutes.push(minute),
-            Less => (),
         }
     }
-    (minutes, highest_minute_count_seen)
-}
 
-#[aoc(day4, part1)]
-pub fn day4_part1(input: &str) -> Answer {
-    let mut midnight_sleep_minute_counts = BTreeMap::new();
-    let midnight_sleep_minutes = process_input(input, |guard_id, (start_minute, end_minute)| {
-        *midnight_sleep_minute_counts.entry(guard_id).or_insert(0u32) +=
-            (end_minute - start_minute).0 as u32
-    });
-
-    let sleepiest_guard = {
-        let mut iter = midnight_sleep_minute_counts.iter();
-        let (mut sleepiest_guard, mut most_seen) = iter.next().unwrap();
-        let mut equals_seen = 0usize;
-        for (guard, minutes_asleep) in iter {
-            match minutes_asleep.cmp(most_seen) {
+    /// How many times the guard was asleep during each minute, starting at the window's start.
+    pub fn counts(&self) -> &[u32] {
+        &self.counts
+    }
+
+    /// How many minutes the guard was asleep within the window, over every night.
+    pub fn total(&self) -> u64 {
+        self.counts.iter().map(|&c| u64::from(c)).sum()
+    }
+
+    /// The buckets with the highest count, and that count.
+    pub fn most_common(&self) -> (Vec<usize>, u32) {
+        let mut minutes = Vec::new();
+        let mut highest_minute_count_seen = 0;
+        for (minute, &count) in self.counts.iter().enumerate() {
+            match count.cmp(&highest_minute_count_seen) {
                 Greater => {
-                    most_seen = minutes_asleep;
-                    sleepiest_guard = guard;
-                    equals_seen = 0;
+                    highest_minute_count_seen = count;
+                    minutes.clear();
+                    minutes.push(minute);
                 }
-                Equal => equals_seen += 1,
+                Equal => minutes.push(minute),
                 Less => (),
             }
         }
-        assert_eq!(equals_seen, 0);
-        sleepiest_guard
-    };
+        (minutes, highest_minute_count_seen)
+    }
+}
 
-    let most_common_minute = {
-        let (minutes, _) =
-            most_common_minutes(midnight_sleep_minutes.get(sleepiest_guard).unwrap());
-        assert_eq!(minutes.len(), 1);
-        minutes[0]
-    };
+/// A single shift of a guard, from the time they began it until the next guard began theirs.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Shift {
+    pub guard_id: GuardId,
+    pub start: NaiveDateTime,
+    /// Naps taken during the shift, in chronological order
+    pub naps: Vec<SleepInterval>,
+}
+
+impl Shift {
+    /// The day whose midnight hour the shift is for. Shifts begun in the evening are for the next
+    /// day.
+    pub fn date(&self) -> NaiveDate {
+        if self.start.hour() >= 12 {
+            self.start.date().succ()
+        } else {
+            self.start.date()
+        }
+    }
+
+    pub fn total_sleep(&self) -> Duration {
+        self.naps
+            .iter()
+            .fold(Duration::zero(), |total, nap| total + nap.duration())
+    }
+}
+
+/// Every shift from a guard log, in chronological order.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct GuardLog {
+    shifts: Vec<Shift>,
+}
+
+impl GuardLog {
+    pub fn parse(input: &str) -> Result<Self, GuardLogError> {
+        use self::GuardEvent::*;
+
+        let mut shifts = Vec::<Shift>::new();
+        let mut start_time = None;
+        for LogEntry { time, event } in parse_guard_log(input)? {
+            match event {
+                ShiftChange { new_guard_id } => shifts.push(Shift {
+                    guard_id: new_guard_id,
+                    start: time,
+                    naps: Vec::new(),
+                }),
+                FallAsleep => start_time = Some(time),
+                // `parse_guard_log` made sure that somebody is on duty and asleep.
+                WakeUp => {
+                    let shift = shifts.last_mut().unwrap();
+                    shift.naps.push(SleepInterval {
+                        guard_id: shift.guard_id,
+                        start: start_time.take().unwrap(),
+                        end: time,
+                    });
+                }
+            }
+        }
+        Ok(Self { shifts })
+    }
+
+    pub fn shifts(&self) -> &[Shift] {
+        &self.shifts
+    }
+
+    pub fn shifts_for(&self, guard_id: GuardId) -> impl Iterator<Item = &Shift> {
+        self.shifts.iter().filter(move |s| s.guard_id == guard_id)
+    }
+
+    /// Every guard that worked a shift, in order of ID, including guards who never fell asleep.
+    pub fn guards(&self) -> BTreeSet<GuardId> {
+        self.shifts.iter().map(|s| s.guard_id).collect()
+    }
+
+    /// Every guard that took at least one nap, in order of ID.
+    pub fn sleeping_guards(&self) -> BTreeSet<GuardId> {
+        self.naps().map(|nap| nap.guard_id).collect()
+    }
+
+    /// Every nap taken by any guard, in chronological order.
+    pub fn naps(&self) -> impl Iterator<Item = &SleepInterval> {
+        self.shifts.iter().flat_map(|s| s.naps.iter())
+    }
+
+    pub fn total_sleep(&self, guard_id: GuardId) -> Duration {
+        self.shifts_for(guard_id)
+            .fold(Duration::zero(), |total, shift| total + shift.total_sleep())
+    }
+
+    /// Every nap taken during a shift for `date`, as decided by `Shift::date`, so naps begun the
+    /// evening before count toward the next day.
+    pub fn sleep_on(&self, date: NaiveDate) -> impl Iterator<Item = &SleepInterval> {
+        self.shifts
+            .iter()
+            .filter(move |s| s.date() == date)
+            .flat_map(|s| s.naps.iter())
+    }
+
+    /// The guards asleep during the minute starting at `time`.
+    pub fn guards_asleep_at(&self, time: NaiveDateTime) -> BTreeSet<GuardId> {
+        self.naps()
+            .filter(|nap| nap.start <= time && time < nap.end)
+            .map(|nap| nap.guard_id)
+            .collect()
+    }
+
+    /// How many times `guard_id` was asleep during each minute of `window`.
+    pub fn histogram(&self, guard_id: GuardId, window: MinuteWindow) -> MinuteHistogram {
+        let mut histogram = MinuteHistogram::new(window);
+        for nap in self.shifts_for(guard_id).flat_map(|s| s.naps.iter()) {
+            histogram.add(nap);
+        }
+        histogram
+    }
+}
+
+impl FromStr for GuardLog {
+    type Err = GuardLogError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl Answer {
+    /// Answers with the time of day `bucket` of `window` starts at, so that buckets in different
+    /// hours of a long window stay distinct.
+    fn in_window(guard_id: GuardId, window: MinuteWindow, bucket: usize) -> Self {
+        Self {
+            guard_id,
+            time: window.bucket_start(bucket),
+        }
+    }
+
+    pub fn guard_id(&self) -> GuardId {
+        self.guard_id
+    }
+
+    /// The time of day the answer's minute starts at.
+    pub fn time(&self) -> NaiveTime {
+        self.time
+    }
+
+    /// The minute of the hour, which is all the puzzle's answer uses.
+    pub fn minute(&self) -> u8 {
+        self.time.minute() as u8
+    }
+}
+
+/// Which of several answers tied for a strategy's maximum to give as the primary one.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TieBreak {
+    /// The lowest guard ID, then the earliest time of day.
+    Lowest,
+    /// The highest guard ID, then the latest time of day.
+    Highest,
+}
+
+impl Default for TieBreak {
+    fn default() -> Self {
+        TieBreak::Lowest
+    }
+}
+
+/// Every guard/minute combination tied for a strategy's maximum.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TiedAnswers {
+    primary: Answer,
+    tied: Vec<Answer>,
+}
+
+impl TiedAnswers {
+    /// Returns `None` if `tied` is empty.
+    fn new(mut tied: Vec<Answer>, tie_break: TieBreak) -> Option<Self> {
+        tied.sort();
+        tied.dedup();
+        let primary = match tie_break {
+            TieBreak::Lowest => tied.first(),
+            TieBreak::Highest => tied.last(),
+        };
+        Some(Self {
+            primary: *primary?,
+            tied,
+        })
+    }
+
+    pub fn primary(&self) -> Answer {
+        self.primary
+    }
 
-    Answer {
-        minute: most_common_minute,
-        guard_id: *sleepiest_guard,
+    /// Every tied answer, lowest first.
+    pub fn tied(&self) -> &[Answer] {
+        &self.tied
+    }
+
+    pub fn is_tie(&self) -> bool {
+        self.tied.len() > 1
     }
 }
 
+/// Keeps every item with the greatest key.
+fn all_max_by_key<T, K: Ord>(items: impl IntoIterator<Item = T>, key: impl Fn(&T) -> K) -> Vec<T> {
+    let mut max_items = Vec::new();
+    let mut max_key = None;
+    for item in items {
+        let k = key(&item);
+        match max_key.as_ref().map_or(Greater, |max| k.cmp(max)) {
+            Greater => {
+                max_key = Some(k);
+                max_items.clear();
+                max_items.push(item);
+            }
+            Equal => max_items.push(item),
+            Less => (),
+        }
+    }
+    max_items
+}
+
+#[aoc(day4, part1)]
+pub fn day4_part1(input: &str) -> Result<Answer, Day4Error> {
+    day4_part1_in_window(input, MinuteWindow::midnight_hour())?.ok_or(Day4Error::NobodySlept)
+}
+
+/// Like `day4_part1`, but only counts minutes of sleep within `window`, and gives no answer if
+/// nobody slept during them.
+pub fn day4_part1_in_window(
+    input: &str,
+    window: MinuteWindow,
+) -> Result<Option<Answer>, GuardLogError> {
+    Ok(day4_part1_tied(input, window, TieBreak::default())?.map(|answers| answers.primary()))
+}
+
+/// Finds the guards who slept the most within `window`, and the minutes each of them was most
+/// often asleep. Guards who never slept within `window` are left out, so there's no answer if
+/// nobody did.
+pub fn day4_part1_tied(
+    input: &str,
+    window: MinuteWindow,
+    tie_break: TieBreak,
+) -> Result<Option<TiedAnswers>, GuardLogError> {
+    let log = GuardLog::parse(input)?;
+    let histograms = log
+        .guards()
+        .into_iter()
+        .map(|guard_id| (guard_id, log.histogram(guard_id, window)))
+        .filter(|(_, h)| h.total() > 0);
+    let sleepiest_guards = all_max_by_key(histograms, |(_, h)| h.total());
+
+    let answers = sleepiest_guards
+        .into_iter()
+        .flat_map(|(guard_id, histogram)| {
+            let (minutes, _) = histogram.most_common();
+            minutes
+                .into_iter()
+                .map(move |bucket| Answer::in_window(guard_id, window, bucket))
+        })
+        .collect();
+    Ok(TiedAnswers::new(answers, tie_break))
+}
+
 #[test]
 fn test_day4_part1_answer() {
     assert_eq!(
         day4_part1(INPUT),
-        Answer {
+        Ok(Answer {
             guard_id: GuardId(641),
-            minute: Minute(41),
-        }
+            time: NaiveTime::from_hms(0, 41, 0),
+        })
     );
 }
 
 #[aoc(day4, part2)]
-pub fn day4_part2(input: &str) -> Answer {
-    let mut log = process_input(input, |_, _| {}).into_iter();
-    let mut next_guard_minutes = || log.next().map(|(g, m)| (g, most_common_minutes(&m)));
-    let (
-        mut guard_with_highest_minute_count,
-        (mut most_common_minutes_for_guard, mut highest_minute_count_seen),
-    ) = next_guard_minutes().unwrap();
-    while let Some((guard, (most_common_minutes, greatest_minute_count))) = next_guard_minutes() {
-        match greatest_minute_count.cmp(&highest_minute_count_seen) {
-            Greater => {
-                guard_with_highest_minute_count = guard;
-                most_common_minutes_for_guard = most_common_minutes;
-                highest_minute_count_seen = greatest_minute_count;
-            }
-            Equal | Less => (),
-        }
-    }
-    assert_eq!(most_common_minutes_for_guard.len(), 1);
-    Answer {
-        guard_id: guard_with_highest_minute_count,
-        minute: most_common_minutes_for_guard[0],
-    }
+pub fn day4_part2(input: &str) -> Result<Answer, Day4Error> {
+    day4_part2_in_window(input, MinuteWindow::midnight_hour())?.ok_or(Day4Error::NobodySlept)
+}
+
+/// Like `day4_part2`, but only counts minutes of sleep within `window`, and gives no answer if
+/// nobody slept during them.
+pub fn day4_part2_in_window(
+    input: &str,
+    window: MinuteWindow,
+) -> Result<Option<Answer>, GuardLogError> {
+    Ok(day4_part2_tied(input, window, TieBreak::default())?.map(|answers| answers.primary()))
+}
+
+/// Finds every guard and minute within `window` that the guard was most often asleep during. Minutes
+/// nobody slept during are left out, so there's no answer if nobody slept within `window`.
+pub fn day4_part2_tied(
+    input: &str,
+    window: MinuteWindow,
+    tie_break: TieBreak,
+) -> Result<Option<TiedAnswers>, GuardLogError> {
+    let log = GuardLog::parse(input)?;
+    let most_common_minutes = log
+        .guards()
+        .into_iter()
+        .map(|guard_id| (guard_id, log.histogram(guard_id, window).most_common()))
+        .filter(|(_, (_, count))| *count > 0);
+
+    let answers = all_max_by_key(most_common_minutes, |(_, (_, count))| *count)
+        .into_iter()
+        .flat_map(|(guard_id, (minutes, _))| {
+            minutes
+                .into_iter()
+                .map(move |bucket| Answer::in_window(guard_id, window, bucket))
+        })
+        .collect();
+    Ok(TiedAnswers::new(answers, tie_break))
 }
 
 #[test]
 fn test_day4_part2_hint() {
     assert_eq!(
         day4_part2(HINT_INPUT),
-        Answer {
+        Ok(Answer {
             guard_id: GuardId(99),
-            minute: Minute(45),
-        }
+            time: NaiveTime::from_hms(0, 45, 0),
+        })
     );
 }
 
@@ -290,9 +742,473 @@ fn test_day4_part2_hint() {
 fn test_day4_part2_answer() {
     assert_eq!(
         day4_part2(INPUT),
-        Answer {
+        Ok(Answer {
             guard_id: GuardId(1973),
-            minute: Minute(37),
+            time: NaiveTime::from_hms(0, 37, 0),
+        })
+    );
+}
+
+#[cfg(test)]
+const LATE_NAPS_INPUT: &str = "[1518-11-01 23:45] Guard #10 begins shift
+[1518-11-01 23:50] falls asleep
+[1518-11-02 01:05] wakes up
+[1518-11-02 23:58] Guard #99 begins shift
+[1518-11-03 00:10] falls asleep
+[1518-11-03 00:20] wakes up
+[1518-11-03 00:30] falls asleep
+[1518-11-03 00:40] wakes up
+[1518-11-03 23:55] Guard #99 begins shift
+[1518-11-04 00:15] falls asleep
+[1518-11-04 00:16] wakes up
+[1518-11-05 00:00] Guard #10 begins shift
+[1518-11-05 00:59] falls asleep
+[1518-11-05 01:01] wakes up
+[1518-11-05 23:57] Guard #10 begins shift
+[1518-11-05 23:58] falls asleep
+[1518-11-05 23:59] wakes up
+[1518-11-07 00:00] Guard #99 begins shift
+[1518-11-07 00:15] falls asleep
+[1518-11-07 00:16] wakes up
+";
+
+#[test]
+fn test_sleep_intervals() {
+    let log = GuardLog::parse(LATE_NAPS_INPUT).unwrap();
+    assert_eq!(log.naps().count(), 7);
+    let first = *log.naps().next().unwrap();
+    assert_eq!(first.guard_id, GuardId(10));
+    assert_eq!(first.duration(), Duration::minutes(75));
+    assert_eq!(first.minutes().count(), 75);
+    assert_eq!(
+        first.minutes().nth(10).unwrap(),
+        NaiveDate::from_ymd(1518, 11, 2).and_hms(0, 0, 0)
+    );
+    assert_eq!(
+        first.minutes().last().unwrap(),
+        NaiveDate::from_ymd(1518, 11, 2).and_hms(1, 4, 0)
+    );
+    assert_eq!(
+        parse_sleep_intervals(LATE_NAPS_INPUT).unwrap(),
+        log.naps().cloned().collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_guard_log_queries() {
+    let at = |d, h, m| NaiveDate::from_ymd(1518, 11, d).and_hms(h, m, 0);
+
+    let log: GuardLog = HINT_INPUT.parse().unwrap();
+    assert_eq!(log.shifts().len(), 5);
+    assert_eq!(
+        log.guards().into_iter().collect::<Vec<_>>(),
+        [GuardId(10), GuardId(99)]
+    );
+    assert_eq!(log.shifts()[1].date(), NaiveDate::from_ymd(1518, 11, 2));
+    assert_eq!(log.shifts()[4].date(), NaiveDate::from_ymd(1518, 11, 5));
+    assert_eq!(log.shifts_for(GuardId(99)).count(), 3);
+    assert_eq!(log.total_sleep(GuardId(10)), Duration::minutes(50));
+    assert_eq!(log.total_sleep(GuardId(99)), Duration::minutes(30));
+    assert_eq!(log.total_sleep(GuardId(42)), Duration::zero());
+
+    let wakeful: GuardLog = "[1518-11-01 00:00] Guard #10 begins shift\n\
+                             [1518-11-01 00:05] falls asleep\n\
+                             [1518-11-01 00:25] wakes up\n\
+                             [1518-11-02 00:00] Guard #20 begins shift\n"
+        .parse()
+        .unwrap();
+    assert_eq!(
+        wakeful.guards().into_iter().collect::<Vec<_>>(),
+        [GuardId(10), GuardId(20)]
+    );
+    assert_eq!(
+        wakeful.sleeping_guards().into_iter().collect::<Vec<_>>(),
+        [GuardId(10)]
+    );
+
+    assert_eq!(log.sleep_on(NaiveDate::from_ymd(1518, 11, 1)).count(), 2);
+    assert_eq!(log.sleep_on(NaiveDate::from_ymd(1518, 11, 4)).count(), 1);
+    assert_eq!(log.sleep_on(NaiveDate::from_ymd(1518, 11, 6)).count(), 0);
+
+    assert!(log.guards_asleep_at(at(1, 0, 4)).is_empty());
+    assert_eq!(log.guards_asleep_at(at(1, 0, 5)).len(), 1);
+    assert!(log.guards_asleep_at(at(1, 0, 25)).is_empty());
+    assert!(log.guards_asleep_at(at(5, 0, 45)).contains(&GuardId(99)));
+
+    // Naps count toward the day of the shift they were taken during, even if they began the
+    // evening before.
+    let late = GuardLog::parse(LATE_NAPS_INPUT).unwrap();
+    assert_eq!(late.shifts()[0].date(), NaiveDate::from_ymd(1518, 11, 2));
+    assert_eq!(late.sleep_on(NaiveDate::from_ymd(1518, 11, 1)).count(), 0);
+    assert_eq!(late.sleep_on(NaiveDate::from_ymd(1518, 11, 2)).count(), 1);
+    assert_eq!(late.sleep_on(NaiveDate::from_ymd(1518, 11, 3)).count(), 2);
+    assert_eq!(
+        late.guards_asleep_at(at(1, 23, 55)),
+        late.guards_asleep_at(at(2, 0, 30))
+    );
+}
+
+#[test]
+fn test_minute_window() {
+    let at = |h, m| NaiveDate::from_ymd(1518, 11, 1).and_hms(h, m, 0);
+    let window = MinuteWindow::new(NaiveTime::from_hms(23, 50, 0), 30);
+    assert_eq!(window.bucket_of(at(23, 49)), None);
+    assert_eq!(window.bucket_of(at(23, 50)), Some(0));
+    assert_eq!(window.bucket_of(at(0, 19)), Some(29));
+    assert_eq!(window.bucket_of(at(0, 20)), None);
+    assert_eq!(
+        window.bucket_of(NaiveDate::from_ymd(1518, 11, 1).and_hms(0, 5, 59)),
+        Some(15)
+    );
+    assert_eq!(window.bucket_start(15), NaiveTime::from_hms(0, 5, 0));
+
+    let midnight = MinuteWindow::midnight_hour();
+    assert_eq!(midnight.bucket_of(at(0, 59)), Some(59));
+    assert_eq!(midnight.bucket_of(at(1, 0)), None);
+    let whole_day = MinuteWindow::new(NaiveTime::from_hms(12, 0, 0), MINUTES_PER_DAY);
+    assert_eq!(
+        whole_day.bucket_of(at(11, 59)),
+        Some(MINUTES_PER_DAY as usize - 1)
+    );
+}
+
+#[test]
+fn test_day4_naps_outside_midnight_hour() {
+    let answer = |guard_id, hour, minute| Answer {
+        guard_id: GuardId(guard_id),
+        time: NaiveTime::from_hms(hour, minute, 0),
+    };
+    assert_eq!(day4_part1(LATE_NAPS_INPUT), Ok(answer(10, 0, 59)));
+    assert_eq!(day4_part2(LATE_NAPS_INPUT), Ok(answer(99, 0, 15)));
+
+    let late_evening = MinuteWindow::new(NaiveTime::from_hms(23, 30, 0), 60);
+    assert_eq!(
+        day4_part1_in_window(LATE_NAPS_INPUT, late_evening),
+        Ok(Some(answer(10, 23, 58)))
+    );
+    assert_eq!(
+        day4_part2_in_window(LATE_NAPS_INPUT, late_evening),
+        Ok(Some(answer(99, 0, 15)))
+    );
+}
+
+#[test]
+fn test_day4_windows_longer_than_an_hour() {
+    let mut input = "[1518-11-01 00:00] Guard #10 begins shift
+[1518-11-01 00:10] falls asleep
+[1518-11-01 00:11] wakes up
+[1518-11-01 01:10] falls asleep
+[1518-11-01 01:12] wakes up
+[1518-11-02 00:00] Guard #10 begins shift
+[1518-11-02 01:10] falls asleep
+[1518-11-02 01:11] wakes up
+"
+    .to_owned();
+    let at = |hour, minute| Answer {
+        guard_id: GuardId(10),
+        time: NaiveTime::from_hms(hour, minute, 0),
+    };
+    let two_hours = MinuteWindow::new(NaiveTime::from_hms(0, 0, 0), 120);
+
+    // 01:10 is the top bucket, even though 00:10 shares its minute of the hour.
+    let answer = day4_part1_in_window(&input, two_hours).unwrap().unwrap();
+    assert_eq!(answer, at(1, 10));
+    assert_eq!(
+        (answer.time(), answer.minute()),
+        (NaiveTime::from_hms(1, 10, 0), 10)
+    );
+    assert_eq!(answer.to_string(), "100");
+    assert_eq!(day4_part2_in_window(&input, two_hours), Ok(Some(at(1, 10))));
+
+    // Once 00:10 catches up, the two buckets tie rather than collapsing into one answer.
+    input.push_str(
+        "[1518-11-03 00:00] Guard #10 begins shift
+[1518-11-03 00:10] falls asleep
+[1518-11-03 00:11] wakes up
+",
+    );
+    let tied = day4_part2_tied(&input, two_hours, TieBreak::Highest)
+        .unwrap()
+        .unwrap();
+    assert_eq!(tied.tied(), &[at(0, 10), at(1, 10)][..]);
+    assert_eq!(tied.primary(), at(1, 10));
+}
+
+#[test]
+fn test_minute_histogram() {
+    let night = |day: NaiveDate, start_minute, end_minute| SleepInterval {
+        guard_id: GuardId(10),
+        start: day.and_hms(0, start_minute, 0),
+        end: day.and_hms(0, 0, 0) + Duration::minutes(end_minute),
+    };
+    let first_night = NaiveDate::from_ymd(1518, 3, 1);
+
+    let mut histogram = MinuteHistogram::new(MinuteWindow::midnight_hour());
+    assert_eq!(histogram.counts().len(), 60);
+    histogram.add(&night(first_night, 57, 61));
+    assert_eq!(&histogram.counts()[56..], [0, 1, 1, 1]);
+    assert_eq!(histogram.total(), 3);
+    assert_eq!(histogram.most_common(), (vec![57, 58, 59], 1));
+
+    for day in 1..300 {
+        histogram.add(&night(first_night + Duration::days(day), 59, 60));
+    }
+    assert_eq!(histogram.counts()[59], 300);
+    assert_eq!(histogram.most_common(), (vec![59], 300));
+    assert_eq!(histogram.total(), 302);
+}
+
+#[test]
+fn test_day4_hundreds_of_nights() {
+    let first_night = NaiveDate::from_ymd(1518, 3, 1);
+    let mut input = String::new();
+    for day in 0..310 {
+        let night = (first_night + Duration::days(day)).format("%Y-%m-%d");
+        let naps: &[_] = match day {
+            0..=299 if day % 2 == 0 => &[("00:30", "00:40"), ("00:59", "01:00")],
+            0..=299 => &[("00:59", "01:00")],
+            _ => &[("00:01", "00:51")],
+        };
+        let guard = if day < 300 { 10 } else { 99 };
+        input.push_str(&format!(
+            "[{} 00:00] Guard #{} begins shift\n",
+            night, guard
+        ));
+        for (start, end) in naps {
+            input.push_str(&format!("[{} {}] falls asleep\n", night, start));
+            input.push_str(&format!("[{} {}] wakes up\n", night, end));
+        }
+    }
+
+    let answer = Answer {
+        guard_id: GuardId(10),
+        time: NaiveTime::from_hms(0, 59, 0),
+    };
+    assert_eq!(day4_part1(&input), Ok(answer));
+    assert_eq!(day4_part2(&input), Ok(answer));
+
+    let log = GuardLog::parse(&input).unwrap();
+    let histogram = |guard| log.histogram(GuardId(guard), MinuteWindow::midnight_hour());
+    assert_eq!(histogram(10).counts()[59], 300);
+    assert_eq!(histogram(10).counts()[30], 150);
+    assert_eq!(histogram(10).total(), 1800);
+    assert_eq!(histogram(99).most_common().1, 10);
+    assert_eq!(log.shifts_for(GuardId(10)).count(), 300);
+}
+
+#[test]
+fn test_guard_log_errors() {
+    use self::GuardLogError::*;
+
+    let error = |input: &str| parse_guard_log(input).unwrap_err();
+    let line = |line_number, line: &str| (line_number, line.to_owned());
+    let unpack = |e: GuardLogError| {
+        let (line_number, line) = e.line();
+        (line_number, line.to_owned())
+    };
+
+    assert_eq!(
+        error("[1518-11-01 00:00] Guard #10 begins shift\n1518-11-01 00:05 falls asleep\n"),
+        MalformedTimestamp {
+            line_number: 2,
+            line: "1518-11-01 00:05 falls asleep".to_owned()
+        }
+    );
+    assert_eq!(
+        unpack(error("[1518-13-01 00:00] Guard #10 begins shift\n")),
+        line(1, "[1518-13-01 00:00] Guard #10 begins shift")
+    );
+
+    for event in &[
+        "dozes off",
+        "Guard #ten begins shift",
+        "Guard #10 begins shift twice",
+    ] {
+        let input = format!("[1518-11-01 00:00] {}", event);
+        assert_eq!(
+            error(&input),
+            UnknownEvent {
+                line_number: 1,
+                line: input.clone()
+            }
+        );
+    }
+
+    assert_eq!(
+        error("[1518-11-01 00:05] falls asleep\n[1518-11-01 00:25] wakes up\n"),
+        SleepWithoutGuard {
+            line_number: 1,
+            line: "[1518-11-01 00:05] falls asleep".to_owned()
+        }
+    );
+    assert_eq!(
+        error("[1518-11-01 00:00] Guard #10 begins shift\n[1518-11-01 00:25] wakes up\n"),
+        WakeWithoutSleep {
+            line_number: 2,
+            line: "[1518-11-01 00:25] wakes up".to_owned()
         }
     );
+    assert_eq!(
+        error(
+            "[1518-11-01 00:00] Guard #10 begins shift\n\
+             [1518-11-01 00:05] falls asleep\n\
+             [1518-11-02 00:00] Guard #99 begins shift\n"
+        ),
+        SleepWithoutWake {
+            line_number: 2,
+            line: "[1518-11-01 00:05] falls asleep".to_owned()
+        }
+    );
+    let e = error(
+        "[1518-11-01 00:00] Guard #10 begins shift\n\
+         [1518-11-01 00:05] falls asleep\n\
+         [1518-11-01 00:05] wakes up\n",
+    );
+    assert_eq!(unpack(e.clone()), line(3, "[1518-11-01 00:05] wakes up"));
+    assert_eq!(
+        e.to_string(),
+        "line 3 (\"[1518-11-01 00:05] wakes up\"): another event happened at the same time"
+    );
+
+    // Events may be logged out of order, and lines may have stray whitespace.
+    let log = parse_guard_log(
+        "[1518-11-01 00:25] wakes up\r\n  [1518-11-01 00:05] falls asleep\n\n\
+         [1518-11-01 00:00] Guard #10 begins shift\r\n",
+    )
+    .unwrap();
+    assert_eq!(
+        log.iter().map(|e| e.event).collect::<Vec<_>>(),
+        [
+            GuardEvent::ShiftChange {
+                new_guard_id: GuardId(10)
+            },
+            GuardEvent::FallAsleep,
+            GuardEvent::WakeUp,
+        ]
+    );
+
+    // The solvers report malformed logs rather than panicking.
+    let malformed = "[1518-11-01 00:05] falls asleep\n[1518-11-01 00:25] wakes up\n";
+    let expected = Err(Day4Error::Log(SleepWithoutGuard {
+        line_number: 1,
+        line: "[1518-11-01 00:05] falls asleep".to_owned(),
+    }));
+    assert_eq!(day4_part1(malformed), expected);
+    assert_eq!(day4_part2(malformed), expected);
+}
+
+#[test]
+fn test_day4_ties() {
+    let input = "[1518-11-01 00:00] Guard #10 begins shift
+[1518-11-01 00:10] falls asleep
+[1518-11-01 00:12] wakes up
+[1518-11-02 00:00] Guard #20 begins shift
+[1518-11-02 00:40] falls asleep
+[1518-11-02 00:44] wakes up
+[1518-11-03 00:00] Guard #30 begins shift
+[1518-11-03 00:50] falls asleep
+[1518-11-03 00:52] wakes up
+[1518-11-04 00:00] Guard #10 begins shift
+[1518-11-04 00:11] falls asleep
+[1518-11-04 00:13] wakes up
+[1518-11-05 00:00] Guard #30 begins shift
+[1518-11-05 00:51] falls asleep
+[1518-11-05 00:53] wakes up
+";
+    let answer = |guard, minute| Answer {
+        guard_id: GuardId(guard),
+        time: NaiveTime::from_hms(0, minute, 0),
+    };
+    let window = MinuteWindow::midnight_hour();
+
+    // Every guard slept for four minutes.
+    let part1 = day4_part1_tied(input, window, TieBreak::Lowest)
+        .unwrap()
+        .unwrap();
+    assert!(part1.is_tie());
+    assert_eq!(
+        part1.tied(),
+        &[
+            answer(10, 11),
+            answer(20, 40),
+            answer(20, 41),
+            answer(20, 42),
+            answer(20, 43),
+            answer(30, 51),
+        ][..]
+    );
+    assert_eq!(part1.primary(), answer(10, 11));
+    assert_eq!(
+        day4_part1_tied(input, window, TieBreak::Highest)
+            .unwrap()
+            .unwrap()
+            .primary(),
+        answer(30, 51)
+    );
+    assert_eq!(day4_part1(input), Ok(answer(10, 11)));
+
+    let part2 = day4_part2_tied(input, window, TieBreak::Highest)
+        .unwrap()
+        .unwrap();
+    assert_eq!(part2.tied(), &[answer(10, 11), answer(30, 51)][..]);
+    assert_eq!(part2.primary(), answer(30, 51));
+    assert_eq!(day4_part2(input), Ok(answer(10, 11)));
+
+    let hint = day4_part2_tied(HINT_INPUT, window, TieBreak::Highest)
+        .unwrap()
+        .unwrap();
+    assert!(!hint.is_tie());
+    assert_eq!(hint.primary(), answer(99, 45));
+    assert_eq!(
+        (hint.primary().guard_id(), hint.primary().minute()),
+        (GuardId(99), 45)
+    );
+}
+
+#[test]
+fn test_day4_nobody_sleeps() {
+    let window = MinuteWindow::midnight_hour();
+
+    let shifts_only = "[1518-11-01 00:00] Guard #10 begins shift
+[1518-11-02 00:00] Guard #20 begins shift
+";
+    assert_eq!(day4_part1(shifts_only), Err(Day4Error::NobodySlept));
+    assert_eq!(day4_part2(shifts_only), Err(Day4Error::NobodySlept));
+    assert_eq!(day4_part1_in_window(shifts_only, window), Ok(None));
+    assert_eq!(day4_part2_in_window(shifts_only, window), Ok(None));
+    assert_eq!(
+        day4_part1_tied(shifts_only, window, TieBreak::Highest),
+        Ok(None)
+    );
+    assert_eq!(
+        day4_part2_tied(shifts_only, window, TieBreak::Highest),
+        Ok(None)
+    );
+    assert_eq!(day4_part1(""), Err(Day4Error::NobodySlept));
+
+    // Sleeping outside the window counts for as little as never sleeping.
+    let after_hours = "[1518-11-01 00:00] Guard #10 begins shift
+[1518-11-02 00:00] Guard #20 begins shift
+[1518-11-02 01:30] falls asleep
+[1518-11-02 01:35] wakes up
+";
+    assert_eq!(day4_part1(after_hours), Err(Day4Error::NobodySlept));
+    assert_eq!(day4_part2(after_hours), Err(Day4Error::NobodySlept));
+    let small_hours = MinuteWindow::new(NaiveTime::from_hms(1, 0, 0), 60);
+    let answer = Answer {
+        guard_id: GuardId(20),
+        time: NaiveTime::from_hms(1, 30, 0),
+    };
+    assert_eq!(
+        day4_part1_in_window(after_hours, small_hours),
+        Ok(Some(answer))
+    );
+    assert_eq!(
+        day4_part2_in_window(after_hours, small_hours),
+        Ok(Some(answer))
+    );
+    assert_eq!(
+        Day4Error::NobodySlept.to_string(),
+        "no guard was ever asleep"
+    );
 }
@@ -0,0 +1,933 @@
+use std::{
+    cmp::max,
+    collections::HashMap,
+    mem::replace,
+    ops::{Index, IndexMut, Range},
+};
+
+/// A dense 2D grid that can grow in any direction, but never shrinks.
+///
+/// Cells are addressed by absolute `(x, y)` coordinates. A grid starts out covering `0..len_x` and
+/// `0..len_y`, but growing it left or up moves its origin (the coordinates of its top-left cell)
+/// into negative coordinates, while every existing cell keeps its coordinates. Indexing only
+/// reaches cells with non-negative coordinates; `get` and `get_mut` reach any cell.
+///
+/// Cells are stored row by row in a buffer with spare capacity on each side. Whenever growing the
+/// grid needs more capacity than that, the capacity is at least doubled in the direction it's
+/// growing in, so growing a grid a little at a time takes amortized time proportional to the
+/// number of new cells.
+#[derive(Clone, Debug)]
+pub struct GrowOnlyGrid<T> {
+    /// Rows of `capacity.0` cells each, including spare cells around the grid's own cells
+    inner: Vec<T>,
+    capacity: (usize, usize),
+    /// The column and row of `inner` holding the top-left cell
+    offset: (usize, usize),
+    origin: (isize, isize),
+    len_x: usize,
+    len_y: usize,
+}
+
+/// Returned when growing a grid would take it past the largest coordinates it can address.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CoordinateOverflow;
+
+fn signed_coords((x, y): (usize, usize)) -> Option<(isize, isize)> {
+    if x > isize::max_value() as usize || y > isize::max_value() as usize {
+        None
+    } else {
+        Some((x as isize, y as isize))
+    }
+}
+
+/// The offset of `coord` from `start`, if it is within `start..start + len`.
+fn offset_within(coord: isize, start: isize, len: usize) -> Option<usize> {
+    let offset = coord.checked_sub(start)?;
+    if offset >= 0 && (offset as usize) < len {
+        Some(offset as usize)
+    } else {
+        None
+    }
+}
+
+fn offset_coords((x, y): (isize, isize), (dx, dy): (isize, isize)) -> Option<(isize, isize)> {
+    Some((x.checked_add(dx)?, y.checked_add(dy)?))
+}
+
+fn push_with<T, F: FnMut() -> T>(values: &mut Vec<T>, count: usize, f: &mut F) {
+    for _ in 0..count {
+        values.push(f());
+    }
+}
+
+fn fill_with<T, F: FnMut() -> T>(values: &mut [T], f: &mut F) {
+    for value in values {
+        *value = f();
+    }
+}
+
+fn drop_values<I: Iterator>(values: &mut I, count: usize) {
+    for _ in values.take(count) {}
+}
+
+/// Picks a new capacity for one dimension of a grid with `len` cells and the given spare cells
+/// before and after them, which needs room for `before` more cells before them and `after` more
+/// after. Returns the new offset of the grid's cells and the new capacity.
+///
+/// Spare cells on a side that isn't growing are kept, so that growing in alternating directions
+/// doesn't throw away capacity on every reallocation.
+fn grown_capacity(
+    len: usize,
+    (spare_before, spare_after): (usize, usize),
+    before: usize,
+    after: usize,
+) -> (usize, usize) {
+    let kept_before = if before == 0 { spare_before } else { 0 };
+    let kept_after = if after == 0 { spare_after } else { 0 };
+    let capacity = spare_before + len + spare_after;
+    let required = [before, after, kept_before, kept_after]
+        .iter()
+        .try_fold(len, |required, &cells| required.checked_add(cells))
+        .unwrap();
+    let new_capacity = max(required, capacity.saturating_mul(2));
+    let extra = new_capacity - required;
+    let extra_before = match (before, after) {
+        (0, _) => 0,
+        (_, 0) => extra,
+        _ => extra / 2,
+    };
+    (kept_before + before + extra_before, new_capacity)
+}
+
+const ORTHOGONAL_OFFSETS: [(isize, isize); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+const ALL_OFFSETS: [(isize, isize); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+impl<T> GrowOnlyGrid<T> {
+    pub fn new_with<F: FnMut() -> T>(x: usize, y: usize, mut f: F) -> Self {
+        Self {
+            inner: {
+                let len = x.checked_mul(y).unwrap();
+                let mut inner = Vec::with_capacity(len);
+                push_with(&mut inner, len, &mut f);
+                inner
+            },
+            capacity: (x, y),
+            offset: (0, 0),
+            origin: (0, 0),
+            len_x: x,
+            len_y: y,
+        }
+    }
+
+    /// Grows the grid right and down until it contains every cell with coordinates less than
+    /// `(x, y)`.
+    ///
+    /// Like every other method growing the grid, this may also call `f` to fill spare capacity.
+    pub fn grow_with<F: FnMut() -> T>(&mut self, x: usize, y: usize, f: F) {
+        let (end_x, end_y) = signed_coords((x, y)).unwrap();
+        let (old_end_x, old_end_y) = self.end();
+        let (origin_x, origin_y) = self.origin;
+        self.regrow_with(
+            self.origin,
+            (
+                (end_x.max(old_end_x) - origin_x) as usize,
+                (end_y.max(old_end_y) - origin_y) as usize,
+            ),
+            f,
+        );
+    }
+
+    /// Grows the grid in whichever directions are needed for it to contain `coords`.
+    pub fn grow_to_include_with<F: FnMut() -> T>(
+        &mut self,
+        (x, y): (isize, isize),
+        f: F,
+    ) -> Result<(), CoordinateOverflow> {
+        let (x_end, y_end) = offset_coords((x, y), (1, 1)).ok_or(CoordinateOverflow)?;
+        if self.len_x == 0 || self.len_y == 0 {
+            self.regrow_with((x, y), (1, 1), f);
+            return Ok(());
+        }
+        let (origin_x, origin_y) = self.origin;
+        let (end_x, end_y) = self.end();
+        let new_origin = (origin_x.min(x), origin_y.min(y));
+        let new_end = (end_x.max(x_end), end_y.max(y_end));
+        let new_len = |end: isize, origin: isize| {
+            end.checked_sub(origin)
+                .map(|len| len as usize)
+                .ok_or(CoordinateOverflow)
+        };
+        let new_len_x = new_len(new_end.0, new_origin.0)?;
+        let new_len_y = new_len(new_end.1, new_origin.1)?;
+        self.regrow_with(new_origin, (new_len_x, new_len_y), f);
+        Ok(())
+    }
+
+    /// Adds the given number of columns or rows to each side of the grid.
+    pub fn grow_by_with<F: FnMut() -> T>(
+        &mut self,
+        left: usize,
+        top: usize,
+        right: usize,
+        bottom: usize,
+        f: F,
+    ) {
+        let (origin_x, origin_y) = self.origin;
+        let (signed_left, signed_top) = signed_coords((left, top)).unwrap();
+        let new_origin = (
+            origin_x.checked_sub(signed_left).unwrap(),
+            origin_y.checked_sub(signed_top).unwrap(),
+        );
+        let new_len_x = self
+            .len_x
+            .checked_add(left)
+            .and_then(|l| l.checked_add(right))
+            .unwrap();
+        let new_len_y = self
+            .len_y
+            .checked_add(top)
+            .and_then(|l| l.checked_add(bottom))
+            .unwrap();
+        self.regrow_with(new_origin, (new_len_x, new_len_y), f);
+    }
+
+    /// Makes sure the grid can grow by `additional_x` columns to the right and `additional_y` rows
+    /// downwards without moving any cells, filling spare capacity with `f`.
+    pub fn reserve_with<F: FnMut() -> T>(
+        &mut self,
+        additional_x: usize,
+        additional_y: usize,
+        mut f: F,
+    ) {
+        self.reserve_sides_with((0, 0, additional_x, additional_y), &mut f);
+    }
+
+    /// Like `reserve_with`, filling spare capacity with default values.
+    pub fn reserve(&mut self, additional_x: usize, additional_y: usize)
+    where
+        T: Default,
+    {
+        self.reserve_with(additional_x, additional_y, T::default);
+    }
+
+    /// Makes room for the given numbers of cells on the left, top, right and bottom of the grid.
+    fn reserve_sides_with<F: FnMut() -> T>(
+        &mut self,
+        (left, top, right, bottom): (usize, usize, usize, usize),
+        f: &mut F,
+    ) {
+        let (capacity_x, capacity_y) = self.capacity;
+        let (offset_x, offset_y) = self.offset;
+        let spare_right = capacity_x - offset_x - self.len_x;
+        let spare_bottom = capacity_y - offset_y - self.len_y;
+        let fits_x = left <= offset_x && right <= spare_right;
+        let fits_y = top <= offset_y && bottom <= spare_bottom;
+        if fits_x && fits_y {
+            return;
+        }
+
+        if fits_x && top <= offset_y {
+            // Only rows at the bottom are missing, which can be appended without moving any cells.
+            let (_, new_capacity_y) =
+                grown_capacity(self.len_y, (offset_y, spare_bottom), 0, bottom);
+            let additional = (new_capacity_y - capacity_y)
+                .checked_mul(capacity_x)
+                .unwrap();
+            self.inner.reserve_exact(additional);
+            push_with(&mut self.inner, additional, f);
+            self.capacity.1 = new_capacity_y;
+            return;
+        }
+
+        let (new_offset_x, new_capacity_x) = if fits_x {
+            (offset_x, capacity_x)
+        } else {
+            grown_capacity(self.len_x, (offset_x, spare_right), left, right)
+        };
+        let (new_offset_y, new_capacity_y) = if fits_y {
+            (offset_y, capacity_y)
+        } else {
+            grown_capacity(self.len_y, (offset_y, spare_bottom), top, bottom)
+        };
+        let new_len = new_capacity_x.checked_mul(new_capacity_y).unwrap();
+
+        // Move the grid's cells over one row at a time, dropping the old spare cells.
+        let mut old_values = replace(&mut self.inner, Vec::with_capacity(new_len)).into_iter();
+        let inner = &mut self.inner;
+        drop_values(&mut old_values, offset_y * capacity_x);
+        push_with(inner, new_offset_y * new_capacity_x, f);
+        for _ in 0..self.len_y {
+            drop_values(&mut old_values, offset_x);
+            push_with(inner, new_offset_x, f);
+            inner.extend(old_values.by_ref().take(self.len_x));
+            push_with(inner, new_capacity_x - new_offset_x - self.len_x, f);
+            drop_values(&mut old_values, capacity_x - offset_x - self.len_x);
+        }
+        push_with(inner, new_len - inner.len(), f);
+
+        self.capacity = (new_capacity_x, new_capacity_y);
+        self.offset = (new_offset_x, new_offset_y);
+    }
+
+    /// Replaces the grid's bounds with new ones that must contain the old ones, filling new cells
+    /// with `f`.
+    fn regrow_with<F: FnMut() -> T>(
+        &mut self,
+        new_origin: (isize, isize),
+        (new_len_x, new_len_y): (usize, usize),
+        mut f: F,
+    ) {
+        if self.len_x == 0 || self.len_y == 0 {
+            // There are no cells to keep in place, so the new ones can go at the top left of the
+            // reserved capacity, which only needs replacing if they don't fit in it.
+            let (capacity_x, capacity_y) = self.capacity;
+            if new_len_x <= capacity_x && new_len_y <= capacity_y {
+                self.len_x = new_len_x;
+                self.len_y = new_len_y;
+                self.offset = (0, 0);
+                for offset_y in 0..new_len_y {
+                    let row = self.row_range(offset_y);
+                    fill_with(&mut self.inner[row], &mut f);
+                }
+            } else {
+                *self = Self::new_with(max(new_len_x, capacity_x), max(new_len_y, capacity_y), f);
+                self.len_x = new_len_x;
+                self.len_y = new_len_y;
+            }
+            self.origin = new_origin;
+            return;
+        }
+
+        let (left, top) = (
+            (self.origin.0 - new_origin.0) as usize,
+            (self.origin.1 - new_origin.1) as usize,
+        );
+        let (old_len_x, old_len_y) = self.dimensions();
+        assert!(
+            left + old_len_x <= new_len_x && top + old_len_y <= new_len_y,
+            "new bounds must contain the old ones"
+        );
+        let (right, bottom) = (new_len_x - left - old_len_x, new_len_y - top - old_len_y);
+        self.reserve_sides_with((left, top, right, bottom), &mut f);
+
+        self.origin = new_origin;
+        self.offset = (self.offset.0 - left, self.offset.1 - top);
+        self.len_x = new_len_x;
+        self.len_y = new_len_y;
+
+        // Spare cells that are now part of the grid might hold stale values.
+        for offset_y in (0..top).chain(top + old_len_y..new_len_y) {
+            let row = self.row_range(offset_y);
+            fill_with(&mut self.inner[row], &mut f);
+        }
+        if left != 0 || right != 0 {
+            for offset_y in top..top + old_len_y {
+                let row = self.row_range(offset_y);
+                fill_with(&mut self.inner[row.start..row.start + left], &mut f);
+                fill_with(&mut self.inner[row.end - right..row.end], &mut f);
+            }
+        }
+    }
+
+    pub fn dimensions(&self) -> (usize, usize) {
+        (self.len_x, self.len_y)
+    }
+
+    /// The coordinates of the top-left cell.
+    pub fn origin(&self) -> (isize, isize) {
+        self.origin
+    }
+
+    /// The coordinates just past the bottom-right cell.
+    fn end(&self) -> (isize, isize) {
+        (
+            self.origin.0 + self.len_x as isize,
+            self.origin.1 + self.len_y as isize,
+        )
+    }
+
+    /// How many columns and rows the grid can hold without moving its cells.
+    pub fn capacity(&self) -> (usize, usize) {
+        self.capacity
+    }
+
+    /// The range of `inner` holding the row `offset_y` rows below the top one.
+    fn row_range(&self, offset_y: usize) -> Range<usize> {
+        let start = (self.offset.1 + offset_y) * self.capacity.0 + self.offset.0;
+        start..start + self.len_x
+    }
+
+    fn index_from_coords(&self, (x, y): (isize, isize)) -> Option<usize> {
+        let offset_x = offset_within(x, self.origin.0, self.len_x)?;
+        let offset_y = offset_within(y, self.origin.1, self.len_y)?;
+        Some(self.row_range(offset_y).start + offset_x)
+    }
+
+    fn expect_index(&self, coords: (isize, isize)) -> usize {
+        match self.index_from_coords(coords) {
+            Some(idx) => idx,
+            None => panic!(
+                "coordinates {:?} are outside of grid with origin {:?} and dimensions {:?}",
+                coords,
+                self.origin,
+                self.dimensions()
+            ),
+        }
+    }
+
+    /// The cell at `coords`, which may be negative unlike when indexing.
+    pub fn get(&self, coords: (isize, isize)) -> Option<&T> {
+        let idx = self.index_from_coords(coords)?;
+        Some(&self.inner[idx])
+    }
+
+    pub fn get_mut(&mut self, coords: (isize, isize)) -> Option<&mut T> {
+        let idx = self.index_from_coords(coords)?;
+        Some(&mut self.inner[idx])
+    }
+
+    /// Every cell, row by row.
+    pub fn iter_flat<'a>(&'a self) -> impl Iterator<Item = &'a T> + 'a {
+        self.rows().flat_map(|row| row.iter())
+    }
+
+    /// Every cell along with its coordinates, row by row.
+    pub fn iter_with_coords<'a>(&'a self) -> impl Iterator<Item = ((isize, isize), &'a T)> + 'a {
+        let (origin_x, origin_y) = self.origin;
+        self.rows().enumerate().flat_map(move |(offset_y, row)| {
+            row.iter().enumerate().map(move |(offset_x, value)| {
+                (
+                    (origin_x + offset_x as isize, origin_y + offset_y as isize),
+                    value,
+                )
+            })
+        })
+    }
+
+    /// Every row from top to bottom.
+    pub fn rows<'a>(&'a self) -> impl Iterator<Item = &'a [T]> + 'a {
+        (0..self.len_y).map(move |offset_y| &self.inner[self.row_range(offset_y)])
+    }
+
+    pub fn row(&self, y: isize) -> Option<&[T]> {
+        let offset_y = offset_within(y, self.origin.1, self.len_y)?;
+        Some(&self.inner[self.row_range(offset_y)])
+    }
+
+    /// The cells of column `x` from top to bottom.
+    pub fn column<'a>(&'a self, x: isize) -> Option<impl Iterator<Item = &'a T> + 'a> {
+        let offset_x = offset_within(x, self.origin.0, self.len_x)?;
+        Some(
+            (0..self.len_y)
+                .map(move |offset_y| &self.inner[self.row_range(offset_y).start + offset_x]),
+        )
+    }
+
+    /// Every column from left to right.
+    pub fn columns<'a>(&'a self) -> impl Iterator<Item = impl Iterator<Item = &'a T> + 'a> + 'a {
+        let origin_x = self.origin.0;
+        (0..self.len_x).map(move |offset_x| self.column(origin_x + offset_x as isize).unwrap())
+    }
+
+    /// A view of the `len_x` by `len_y` cells starting at `origin`, if they are all in the grid.
+    pub fn view(
+        &self,
+        origin: (isize, isize),
+        (len_x, len_y): (usize, usize),
+    ) -> Option<GridView<T>> {
+        let (end_x, end_y) = offset_coords(origin, signed_coords((len_x, len_y))?)?;
+        let (grid_end_x, grid_end_y) = self.end();
+        let contained = origin.0 >= self.origin.0
+            && origin.1 >= self.origin.1
+            && end_x <= grid_end_x
+            && end_y <= grid_end_y;
+        if contained {
+            Some(GridView {
+                grid: self,
+                origin,
+                len_x,
+                len_y,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// The up to four cells directly above, left of, right of and below `coords`.
+    pub fn orthogonal_neighbours<'a>(
+        &'a self,
+        coords: (isize, isize),
+    ) -> impl Iterator<Item = ((isize, isize), &'a T)> + 'a {
+        self.neighbours_at(coords, &ORTHOGONAL_OFFSETS)
+    }
+
+    /// The up to eight cells surrounding `coords`, including diagonal ones.
+    pub fn all_neighbours<'a>(
+        &'a self,
+        coords: (isize, isize),
+    ) -> impl Iterator<Item = ((isize, isize), &'a T)> + 'a {
+        self.neighbours_at(coords, &ALL_OFFSETS)
+    }
+
+    fn neighbours_at<'a>(
+        &'a self,
+        coords: (isize, isize),
+        offsets: &'static [(isize, isize)],
+    ) -> impl Iterator<Item = ((isize, isize), &'a T)> + 'a {
+        offsets.iter().filter_map(move |&offset| {
+            let neighbour = offset_coords(coords, offset)?;
+            Some((neighbour, self.get(neighbour)?))
+        })
+    }
+}
+
+/// Grids are equal when they have the same bounds and equal cells, however much spare capacity
+/// either has.
+impl<T: PartialEq> PartialEq for GrowOnlyGrid<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.origin == other.origin
+            && self.dimensions() == other.dimensions()
+            && self.rows().eq(other.rows())
+    }
+}
+
+impl<T: Eq> Eq for GrowOnlyGrid<T> {}
+
+impl<T> Index<(usize, usize)> for GrowOnlyGrid<T> {
+    type Output = T;
+
+    fn index(&self, coords: (usize, usize)) -> &Self::Output {
+        let idx = self.expect_index(signed_coords(coords).unwrap());
+        &self.inner[idx]
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for GrowOnlyGrid<T> {
+    fn index_mut(&mut self, coords: (usize, usize)) -> &mut Self::Output {
+        let idx = self.expect_index(signed_coords(coords).unwrap());
+        &mut self.inner[idx]
+    }
+}
+
+/// A 2D grid of cells that grows to fit whatever gets written to it, so that algorithms can work
+/// on either a dense `GrowOnlyGrid` or a `SparseGrid`.
+pub trait CellGrid<T>: IndexMut<(usize, usize), Output = T> {
+    /// Makes sure every cell with non-negative coordinates less than `(x, y)` can be indexed.
+    /// Newly added cells hold default values.
+    fn grow_to(&mut self, x: usize, y: usize);
+
+    /// Every cell that might hold something other than a default value.
+    fn stored_cells<'a>(&'a self) -> Box<dyn Iterator<Item = &'a T> + 'a>;
+}
+
+impl<T: Default> CellGrid<T> for GrowOnlyGrid<T> {
+    fn grow_to(&mut self, x: usize, y: usize) {
+        self.grow_with(x, y, Default::default);
+    }
+
+    fn stored_cells<'a>(&'a self) -> Box<dyn Iterator<Item = &'a T> + 'a> {
+        Box::new(self.iter_flat())
+    }
+}
+
+/// A read-only view of a rectangular region of a `GrowOnlyGrid`, using the same coordinates as
+/// the grid.
+#[derive(Clone, Copy, Debug)]
+pub struct GridView<'g, T> {
+    grid: &'g GrowOnlyGrid<T>,
+    origin: (isize, isize),
+    len_x: usize,
+    len_y: usize,
+}
+
+impl<'g, T> GridView<'g, T> {
+    pub fn dimensions(&self) -> (usize, usize) {
+        (self.len_x, self.len_y)
+    }
+
+    pub fn origin(&self) -> (isize, isize) {
+        self.origin
+    }
+
+    pub fn get(&self, (x, y): (isize, isize)) -> Option<&'g T> {
+        offset_within(x, self.origin.0, self.len_x)?;
+        offset_within(y, self.origin.1, self.len_y)?;
+        self.grid.get((x, y))
+    }
+
+    /// Every row of the view from top to bottom.
+    pub fn rows(&self) -> impl Iterator<Item = &'g [T]> {
+        let grid = self.grid;
+        let (origin_x, origin_y) = self.origin;
+        let start_x = (origin_x - grid.origin.0) as usize;
+        let len_x = self.len_x;
+        (origin_y..origin_y + self.len_y as isize)
+            .map(move |y| &grid.row(y).unwrap()[start_x..start_x + len_x])
+    }
+
+    /// Every cell of the view along with its coordinates, row by row.
+    pub fn iter_with_coords(&self) -> impl Iterator<Item = ((isize, isize), &'g T)> {
+        let (origin_x, origin_y) = self.origin;
+        self.rows().enumerate().flat_map(move |(offset_y, row)| {
+            row.iter().enumerate().map(move |(offset_x, value)| {
+                (
+                    (origin_x + offset_x as isize, origin_y + offset_y as isize),
+                    value,
+                )
+            })
+        })
+    }
+}
+
+/// The width and height of each of a `SparseGrid`'s tiles.
+pub const TILE_SIZE: usize = 64;
+
+/// A 2D grid that only stores the `TILE_SIZE`-by-`TILE_SIZE` tiles that have been written to, so it
+/// can span any coordinates as long as most of them are left alone.
+///
+/// Every cell starts out holding the grid's default value, and reading a cell never allocates.
+#[derive(Clone, Debug)]
+pub struct SparseGrid<T> {
+    /// Tiles by their coordinates in units of tiles, each storing its cells row by row
+    tiles: HashMap<(isize, isize), Box<[T]>>,
+    default: T,
+}
+
+/// The coordinates of the tile containing a cell, and the index of the cell within that tile.
+fn tile_and_index((x, y): (isize, isize)) -> ((isize, isize), usize) {
+    let tile_size = TILE_SIZE as isize;
+    (
+        (x.div_euclid(tile_size), y.div_euclid(tile_size)),
+        y.rem_euclid(tile_size) as usize * TILE_SIZE + x.rem_euclid(tile_size) as usize,
+    )
+}
+
+impl<T: Clone> SparseGrid<T> {
+    pub fn new(default: T) -> Self {
+        Self {
+            tiles: HashMap::new(),
+            default,
+        }
+    }
+
+    /// The cell at `coords`, which holds the default value if it was never written.
+    pub fn get(&self, coords: (isize, isize)) -> &T {
+        let (tile, idx) = tile_and_index(coords);
+        match self.tiles.get(&tile) {
+            Some(cells) => &cells[idx],
+            None => &self.default,
+        }
+    }
+
+    /// The cell at `coords`, allocating the tile it's in if needed.
+    pub fn get_mut(&mut self, coords: (isize, isize)) -> &mut T {
+        let (tile, idx) = tile_and_index(coords);
+        let default = &self.default;
+        let cells = self
+            .tiles
+            .entry(tile)
+            .or_insert_with(|| vec![default.clone(); TILE_SIZE * TILE_SIZE].into_boxed_slice());
+        &mut cells[idx]
+    }
+
+    /// How many tiles have been allocated.
+    pub fn tile_count(&self) -> usize {
+        self.tiles.len()
+    }
+
+    /// Every cell of every allocated tile along with its coordinates, in no particular order.
+    pub fn iter_stored<'a>(&'a self) -> impl Iterator<Item = ((isize, isize), &'a T)> + 'a {
+        let tile_size = TILE_SIZE as isize;
+        self.tiles
+            .iter()
+            .flat_map(move |(&(tile_x, tile_y), cells)| {
+                cells.iter().enumerate().map(move |(idx, value)| {
+                    (
+                        (
+                            tile_x * tile_size + (idx % TILE_SIZE) as isize,
+                            tile_y * tile_size + (idx / TILE_SIZE) as isize,
+                        ),
+                        value,
+                    )
+                })
+            })
+    }
+}
+
+impl<T: Clone> Index<(usize, usize)> for SparseGrid<T> {
+    type Output = T;
+
+    fn index(&self, coords: (usize, usize)) -> &Self::Output {
+        self.get(signed_coords(coords).unwrap())
+    }
+}
+
+impl<T: Clone> IndexMut<(usize, usize)> for SparseGrid<T> {
+    fn index_mut(&mut self, coords: (usize, usize)) -> &mut Self::Output {
+        self.get_mut(signed_coords(coords).unwrap())
+    }
+}
+
+impl<T: Clone> CellGrid<T> for SparseGrid<T> {
+    fn grow_to(&mut self, _x: usize, _y: usize) {
+        // Tiles are allocated when they're first written to.
+    }
+
+    fn stored_cells<'a>(&'a self) -> Box<dyn Iterator<Item = &'a T> + 'a> {
+        Box::new(self.tiles.values().flat_map(|cells| cells.iter()))
+    }
+}
+
+#[cfg(test)]
+fn numbered_grid(len_x: usize, len_y: usize) -> GrowOnlyGrid<u32> {
+    let mut next = 0;
+    GrowOnlyGrid::new_with(len_x, len_y, || {
+        next += 1;
+        next
+    })
+}
+
+#[test]
+fn test_grow_in_all_directions() {
+    let mut grid = numbered_grid(3, 2);
+    grid.grow_with(4, 3, || 0);
+    assert_eq!(grid.dimensions(), (4, 3));
+    assert_eq!(
+        grid.rows().collect::<Vec<_>>(),
+        [&[1, 2, 3, 0][..], &[4, 5, 6, 0], &[0, 0, 0, 0]]
+    );
+
+    grid.grow_to_include_with((-2, -1), || 9).unwrap();
+    assert_eq!(grid.origin(), (-2, -1));
+    assert_eq!(grid.dimensions(), (6, 4));
+    assert_eq!(grid.get((-2, -1)), Some(&9));
+    assert_eq!(grid[(0, 0)], 1);
+    assert_eq!(grid[(2, 1)], 6);
+
+    grid.grow_by_with(1, 0, 0, 1, || 7);
+    assert_eq!(grid.origin(), (-3, -1));
+    assert_eq!(grid.dimensions(), (7, 5));
+    assert_eq!(grid.row(1), Some(&[7, 9, 9, 4, 5, 6, 0][..]));
+    assert_eq!(grid.row(3), Some(&[7; 7][..]));
+    assert_eq!(grid.row(4), None);
+
+    let mut empty = GrowOnlyGrid::new_with(0, 0, || 0);
+    empty.grow_to_include_with((-5, 5), || 3).unwrap();
+    assert_eq!((empty.origin(), empty.dimensions()), ((-5, 5), (1, 1)));
+    assert_eq!(empty.get((-5, 5)), Some(&3));
+
+    let max = isize::max_value();
+    assert_eq!(
+        empty.grow_to_include_with((max, 0), || 0),
+        Err(CoordinateOverflow)
+    );
+    assert_eq!(
+        empty.grow_to_include_with((0, max), || 0),
+        Err(CoordinateOverflow)
+    );
+    assert_eq!((empty.origin(), empty.dimensions()), ((-5, 5), (1, 1)));
+}
+
+#[test]
+fn test_safe_indexing() {
+    let mut grid = numbered_grid(2, 2);
+    assert_eq!(grid.get((1, 1)), Some(&4));
+    assert_eq!(grid.get((2, 0)), None);
+    assert_eq!(grid.get((0, -1)), None);
+    assert_eq!(grid.get((isize::min_value(), 0)), None);
+    *grid.get_mut((0, 1)).unwrap() = 10;
+    assert_eq!(grid[(0, 1)], 10);
+    assert!(grid.get_mut((-1, -1)).is_none());
+}
+
+#[test]
+fn test_rows_and_columns() {
+    let grid = numbered_grid(3, 2);
+    assert_eq!(grid.column(1).unwrap().collect::<Vec<_>>(), [&2, &5]);
+    assert!(grid.column(3).is_none());
+    assert_eq!(
+        grid.columns()
+            .map(|c| c.cloned().collect::<Vec<_>>())
+            .collect::<Vec<_>>(),
+        [[1, 4], [2, 5], [3, 6]]
+    );
+    assert_eq!(
+        grid.iter_with_coords().collect::<Vec<_>>(),
+        [
+            ((0, 0), &1),
+            ((1, 0), &2),
+            ((2, 0), &3),
+            ((0, 1), &4),
+            ((1, 1), &5),
+            ((2, 1), &6),
+        ]
+    );
+}
+
+#[test]
+fn test_views() {
+    let mut grid = numbered_grid(4, 4);
+    grid.grow_by_with(1, 1, 0, 0, || 0);
+
+    let view = grid.view((1, 1), (2, 3)).unwrap();
+    assert_eq!(view.dimensions(), (2, 3));
+    assert_eq!(
+        view.rows().collect::<Vec<_>>(),
+        [&[6, 7][..], &[10, 11], &[14, 15]]
+    );
+    assert_eq!(view.get((1, 1)), Some(&6));
+    assert_eq!(view.get((0, 1)), None);
+    assert_eq!(
+        view.iter_with_coords().take(3).collect::<Vec<_>>(),
+        [((1, 1), &6), ((2, 1), &7), ((1, 2), &10)]
+    );
+
+    assert!(grid.view((-1, -1), (5, 5)).is_some());
+    assert!(grid.view((-2, 0), (1, 1)).is_none());
+    assert!(grid.view((2, 2), (2, 2)).is_some());
+    assert!(grid.view((2, 2), (3, 2)).is_none());
+}
+
+#[test]
+fn test_neighbours() {
+    let grid = numbered_grid(3, 3);
+    assert_eq!(
+        grid.orthogonal_neighbours((1, 1)).collect::<Vec<_>>(),
+        [((1, 0), &2), ((0, 1), &4), ((2, 1), &6), ((1, 2), &8)]
+    );
+    assert_eq!(
+        grid.orthogonal_neighbours((0, 0)).collect::<Vec<_>>(),
+        [((1, 0), &2), ((0, 1), &4)]
+    );
+    assert_eq!(
+        grid.all_neighbours((2, 2)).collect::<Vec<_>>(),
+        [((1, 1), &5), ((2, 1), &6), ((1, 2), &8)]
+    );
+    assert_eq!(grid.all_neighbours((1, 1)).count(), 8);
+}
+
+#[test]
+fn test_reserve() {
+    let mut grid = numbered_grid(2, 2);
+    grid.reserve(3, 1);
+    let capacity = grid.capacity();
+    assert!(capacity.0 >= 5 && capacity.1 >= 3);
+    assert_eq!(grid.dimensions(), (2, 2));
+    assert_eq!(grid.iter_flat().collect::<Vec<_>>(), [&1, &2, &3, &4]);
+
+    // Growing into reserved capacity mustn't move any cells, or expose the values they were
+    // reserved with.
+    let first_cell = grid.get((0, 0)).unwrap() as *const u32;
+    grid.grow_with(5, 3, || 8);
+    assert_eq!(grid.get((0, 0)).unwrap() as *const u32, first_cell);
+    assert_eq!(grid.capacity(), capacity);
+    assert_eq!(
+        grid.rows().collect::<Vec<_>>(),
+        [&[1, 2, 8, 8, 8][..], &[3, 4, 8, 8, 8], &[8; 5]]
+    );
+
+    let mut roomy = grid.clone();
+    roomy.reserve(100, 100);
+    assert_ne!(roomy.capacity(), grid.capacity());
+    assert_eq!(roomy, grid);
+    roomy[(4, 2)] = 9;
+    assert_ne!(roomy, grid);
+    assert_ne!(numbered_grid(2, 3), numbered_grid(3, 2));
+
+    // Growing a grid without any cells yet keeps the capacity reserved for it.
+    let mut empty = GrowOnlyGrid::new_with(0, 0, || 0u32);
+    empty.reserve(100, 100);
+    let capacity = empty.capacity();
+    empty.grow_with(5, 5, || 8);
+    assert_eq!(empty.capacity(), capacity);
+    assert_eq!(empty.dimensions(), (5, 5));
+    assert!(empty.iter_flat().all(|&cell| cell == 8));
+    let mut empty = GrowOnlyGrid::new_with(3, 0, || 0u32);
+    empty.reserve(10, 10);
+    let capacity = empty.capacity();
+    empty.grow_to_include_with((-20, -20), || 8).unwrap();
+    assert_eq!(empty.capacity(), capacity);
+    assert_eq!(empty.get((-20, -20)), Some(&8));
+    let mut empty = GrowOnlyGrid::new_with(0, 0, || 0u32);
+    empty.reserve(10, 10);
+    empty.grow_with(200, 1, || 9);
+    assert_eq!(empty.dimensions(), (200, 1));
+    assert_eq!(empty.capacity(), (200, 10));
+    assert!(empty.iter_flat().all(|&cell| cell == 9));
+}
+
+#[test]
+fn test_amortized_growth() {
+    let mut grid = GrowOnlyGrid::new_with(1, 1, || 0u32);
+    let mut reallocations = 0;
+    for i in 1..=1000 {
+        let capacity = grid.capacity();
+        match i % 4 {
+            0 => grid.grow_by_with(1, 0, 0, 0, || i),
+            1 => grid.grow_by_with(0, 1, 0, 0, || i),
+            2 => grid.grow_by_with(0, 0, 1, 0, || i),
+            _ => grid.grow_by_with(0, 0, 0, 1, || i),
+        }
+        if grid.capacity() != capacity {
+            reallocations += 1;
+        }
+    }
+    assert_eq!(grid.dimensions(), (501, 501));
+    assert_eq!(grid.origin(), (-250, -250));
+    assert!(reallocations <= 40, "{} reallocations", reallocations);
+
+    // Each ring of cells around the original one was added by the same four growths.
+    for ((x, y), &value) in grid.iter_with_coords() {
+        let ring = x.abs().max(y.abs()) as u32;
+        let expected = match (x, y) {
+            (0, 0) => 0,
+            _ if x == -(ring as isize) => 4 * ring,
+            _ if y == ring as isize => 4 * ring - 1,
+            _ if x == ring as isize => 4 * ring - 2,
+            _ => 4 * ring - 3,
+        };
+        assert_eq!(value, expected, "at {:?}", (x, y));
+    }
+}
+
+#[test]
+fn test_sparse_grid() {
+    let mut grid = SparseGrid::new(0u32);
+    assert_eq!(grid.get((5, -5)), &0);
+    assert_eq!(grid.tile_count(), 0);
+
+    let far = (1_000_000_000isize, -1_000_000_000isize);
+    *grid.get_mut(far) = 1;
+    *grid.get_mut((-1, -1)) = 2;
+    grid[(0, 0)] = 3;
+    grid[(63, 63)] = 4;
+    assert_eq!(grid.tile_count(), 3);
+    assert_eq!(grid.get(far), &1);
+    assert_eq!(grid.get((-1, -1)), &2);
+    assert_eq!(grid[(0, 0)], 3);
+    assert_eq!(grid[(63, 63)], 4);
+    assert_eq!(grid[(64, 63)], 0);
+
+    let mut stored = grid
+        .iter_stored()
+        .filter(|&(_, &value)| value != 0)
+        .collect::<Vec<_>>();
+    stored.sort();
+    assert_eq!(
+        stored,
+        [((-1, -1), &2), ((0, 0), &3), ((63, 63), &4), (far, &1),]
+    );
+    assert_eq!(
+        grid.stored_cells().count(),
+        grid.tile_count() * TILE_SIZE * TILE_SIZE
+    );
+}
@@ -7,5 +7,6 @@ pub mod day2;
 pub mod day3;
 pub mod day4;
 pub mod day5;
+pub mod grid;
 
 aoc_lib! { year = 2018 }